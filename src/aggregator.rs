@@ -0,0 +1,181 @@
+
+use std::collections::HashMap;
+use chrono::Duration;
+use chrono::datetime::DateTime;
+use chrono::offset::local::Local;
+use super::Message;
+
+/// A change of the squawk code of an aircraft, found by `TrackAggregator::push`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquawkChange {
+    /// The ICAO address of the aircraft
+    pub icao: u32,
+    /// The squawk code in the previous message from the aircraft that had one
+    pub old: u16,
+    /// The squawk code in the new message
+    pub new: u16,
+}
+
+/// Groups messages by the aircraft that sent them
+///
+/// Messages are grouped by ICAO address. Messages without an ICAO address are ignored.
+///
+/// Times are measured using the generated timestamps of the messages, so that recorded feeds
+/// behave in the same way as live ones.
+#[derive(Debug, Clone, Default)]
+pub struct TrackAggregator {
+    /// The messages from each aircraft, in the order they were pushed
+    tracks: HashMap<u32, Vec<Message>>,
+    /// The latest generated time of all messages that have been pushed
+    latest: Option<DateTime<Local>>,
+}
+
+impl TrackAggregator {
+    /// Creates an aggregator with no messages
+    pub fn new() -> TrackAggregator {
+        TrackAggregator::default()
+    }
+
+    /// Adds a message to the track of the aircraft that sent it, and returns any changes of
+    /// squawk code that it shows
+    ///
+    /// The squawk code of the message is compared with the code in the latest earlier message
+    /// from the same aircraft that had one. Messages without a squawk code do not cause changes.
+    pub fn push(&mut self, message: Message) -> Vec<SquawkChange> {
+        let icao_address = match message.icao_address {
+            Some(icao_address) => icao_address,
+            None => return Vec::new(),
+        };
+        if let Some(generated) = message.generated {
+            if self.latest.is_none_or(|latest| generated > latest) {
+                self.latest = Some(generated);
+            }
+        }
+        let track = self.tracks.entry(icao_address).or_default();
+        let previous = track.iter().rev().find_map(|message| message.squawk);
+        let changes = match (previous, message.squawk) {
+            (Some(old), Some(new)) if old != new => {
+                vec![SquawkChange { icao: icao_address, old, new }]
+            }
+            _ => Vec::new(),
+        };
+        track.push(message);
+        changes
+    }
+
+    /// Returns the messages from each aircraft, keyed by ICAO address
+    pub fn tracks(&self) -> &HashMap<u32, Vec<Message>> {
+        &self.tracks
+    }
+
+    /// Returns the mean ground speed of the messages from an aircraft that were generated
+    /// within the provided duration
+    ///
+    /// The duration is measured back from the latest generated time of all messages, as in
+    /// `expire`. Messages without a ground speed or a generated time are ignored. Returns None
+    /// if no messages are left.
+    pub fn average_ground_speed(&self, icao: u32, window: Duration) -> Option<f64> {
+        let cutoff = self.latest? - window;
+        let (total, count) = self.tracks.get(&icao)?.iter()
+            .filter(|message| message.generated.is_some_and(|generated| generated >= cutoff))
+            .filter_map(|message| message.ground_speed)
+            .fold((0.0, 0), |(total, count), speed| (total + speed, count + 1));
+        if count == 0 { None } else { Some(total / count as f64) }
+    }
+
+    /// Removes all aircraft that have not been seen for longer than the provided duration
+    ///
+    /// An aircraft was last seen at the latest generated time of its messages. The duration
+    /// is measured back from the latest generated time of all messages. Aircraft with no
+    /// generated times are removed if any other message has a generated time.
+    pub fn expire(&mut self, older_than: Duration) {
+        let cutoff = match self.latest {
+            Some(latest) => latest - older_than,
+            None => return,
+        };
+        self.tracks.retain(|_, messages| {
+            messages.iter()
+                .filter_map(|message| message.generated)
+                .any(|generated| generated >= cutoff)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::offset::TimeZone;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    /// Creates a position message from an aircraft, generated at the provided second
+    fn position(icao_address: u32, second: u32) -> Message {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.icao_address = Some(icao_address);
+        message.generated = Some(Local.ymd(2016, 3, 11).and_hms(21, 24, second));
+        message
+    }
+
+    #[test]
+    fn test_group_by_aircraft() {
+        let mut aggregator = TrackAggregator::new();
+        aggregator.push(position(0xA1B2C3, 0));
+        aggregator.push(position(0x3C6586, 1));
+        aggregator.push(position(0xA1B2C3, 2));
+        aggregator.push(Message::new(MessageType::Click));
+
+        let tracks = aggregator.tracks();
+        assert_eq!(2, tracks.len());
+        assert_eq!(vec![position(0xA1B2C3, 0), position(0xA1B2C3, 2)], tracks[&0xA1B2C3]);
+        assert_eq!(vec![position(0x3C6586, 1)], tracks[&0x3C6586]);
+    }
+    #[test]
+    fn test_expire() {
+        let mut aggregator = TrackAggregator::new();
+        aggregator.push(position(0x3C6586, 0));
+        aggregator.push(position(0xA1B2C3, 10));
+        aggregator.push(position(0xA1B2C3, 40));
+        aggregator.expire(Duration::seconds(30));
+
+        let tracks = aggregator.tracks();
+        assert_eq!(1, tracks.len());
+        assert_eq!(2, tracks[&0xA1B2C3].len());
+    }
+    #[test]
+    fn test_squawk_change() {
+        let squawk = |second: u32, squawk: Option<u16>| {
+            let mut message = position(0xA1B2C3, second);
+            message.squawk = squawk;
+            message
+        };
+        let mut aggregator = TrackAggregator::new();
+        assert_eq!(Vec::<SquawkChange>::new(), aggregator.push(squawk(0, Some(1000))));
+        assert_eq!(Vec::<SquawkChange>::new(), aggregator.push(squawk(1, None)));
+        assert_eq!(Vec::<SquawkChange>::new(), aggregator.push(squawk(2, Some(1000))));
+        assert_eq!(vec![SquawkChange { icao: 0xA1B2C3, old: 1000, new: 7700 }],
+                   aggregator.push(squawk(3, Some(7700))));
+        // Other aircraft have their own squawk codes
+        let mut other = squawk(4, Some(2000));
+        other.icao_address = Some(0x3C6586);
+        assert_eq!(Vec::<SquawkChange>::new(), aggregator.push(other));
+    }
+    #[test]
+    fn test_average_ground_speed() {
+        let speed = |second: u32, ground_speed: Option<f64>| {
+            let mut message = position(0xA1B2C3, second);
+            message.ground_speed = ground_speed;
+            message
+        };
+        let mut aggregator = TrackAggregator::new();
+        assert_eq!(None, aggregator.average_ground_speed(0xA1B2C3, Duration::seconds(10)));
+        aggregator.push(speed(0, Some(300.0)));
+        aggregator.push(speed(15, Some(440.0)));
+        aggregator.push(speed(20, None));
+        aggregator.push(speed(25, Some(460.0)));
+        assert_eq!(Some(450.0), aggregator.average_ground_speed(0xA1B2C3, Duration::seconds(10)));
+        assert_eq!(Some(400.0), aggregator.average_ground_speed(0xA1B2C3, Duration::seconds(30)));
+        assert_eq!(None, aggregator.average_ground_speed(0x3C6586, Duration::seconds(30)));
+        // Another aircraft moves the latest time forward
+        aggregator.push(position(0x3C6586, 40));
+        assert_eq!(None, aggregator.average_ground_speed(0xA1B2C3, Duration::seconds(10)));
+    }
+}
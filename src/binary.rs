@@ -0,0 +1,455 @@
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt::Display;
+#[cfg(feature = "chrono")]
+use chrono::datetime::DateTime;
+#[cfg(feature = "chrono")]
+use chrono::offset::local::Local;
+#[cfg(feature = "chrono")]
+use chrono::offset::TimeZone;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use super::{AircraftStatus, Message, MessageType, TransmissionType};
+
+// Bits of the presence mask, one for each optional field
+const SESSION_ID: u32 = 1 << 0;
+const AIRCRAFT_ID: u32 = 1 << 1;
+const IDENT: u32 = 1 << 2;
+const ICAO_ADDRESS: u32 = 1 << 3;
+const FLIGHT_ID: u32 = 1 << 4;
+const GENERATED: u32 = 1 << 5;
+const LOGGED: u32 = 1 << 6;
+const CALLSIGN: u32 = 1 << 7;
+const ALTITUDE: u32 = 1 << 8;
+const GROUND_SPEED: u32 = 1 << 9;
+const TRACK: u32 = 1 << 10;
+const LATITUDE: u32 = 1 << 11;
+const LONGITUDE: u32 = 1 << 12;
+const VERTICAL_SPEED: u32 = 1 << 13;
+const SQUAWK: u32 = 1 << 14;
+const ALERT: u32 = 1 << 15;
+const EMERGENCY: u32 = 1 << 16;
+const SPECIAL_POSITION: u32 = 1 << 17;
+const ON_GROUND: u32 = 1 << 18;
+const RSSI: u32 = 1 << 19;
+const EMITTER_CATEGORY: u32 = 1 << 20;
+const STATUS: u32 = 1 << 21;
+const IDENT_RAW: u32 = 1 << 22;
+const POSITION_VALID: u32 = 1 << 23;
+/// The number of bits that the values of the flags are shifted above their presence bits
+const FLAG_VALUE_SHIFT: u32 = 9;
+/// All the bits that the encoding uses, including the values of the flags
+const KNOWN_BITS: u32 = ((1 << 24) - 1) | ((ALERT | EMERGENCY | SPECIAL_POSITION | ON_GROUND) << FLAG_VALUE_SHIFT);
+
+/// The number of fixed-point units in one unit of speed, track, or signal strength
+const HUNDREDTHS: f64 = 100.0;
+/// The number of fixed-point units in one degree of latitude or longitude
+const DEGREE_UNITS: f64 = 10_000_000.0;
+
+/// Errors that can occur when decoding a message from bytes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DecodeError {
+    /// The bytes ended before the end of the message
+    UnexpectedEnd,
+    /// There were more bytes after the end of the message
+    TrailingBytes,
+    /// The message type byte or the presence mask was not valid
+    InvalidHeader,
+    /// The callsign was not valid UTF-8
+    InvalidCallsign,
+    /// The text of the HexIdent column was not valid UTF-8
+    InvalidIdent,
+    /// A timestamp could not be represented
+    InvalidTimestamp,
+    /// The aircraft status code was not valid
+    InvalidStatus,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let problem = match *self {
+            DecodeError::UnexpectedEnd => "Unexpected end of data",
+            DecodeError::TrailingBytes => "Unexpected data after the message",
+            DecodeError::InvalidHeader => "Invalid message header",
+            DecodeError::InvalidCallsign => "Invalid callsign",
+            DecodeError::InvalidIdent => "Invalid HexIdent text",
+            DecodeError::InvalidTimestamp => "Invalid timestamp",
+            DecodeError::InvalidStatus => "Invalid aircraft status",
+        };
+        write!(f, "Decode error: {}", problem)
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl Message {
+    /// Encodes this message in a compact binary format, for archiving
+    ///
+    /// The message starts with a type byte and a four-byte mask of the fields that are present,
+    /// which also holds the values of the flags. The present fields follow in the order of the
+    /// fields of this struct, in little-endian byte order:
+    ///
+    /// * The IDs, the ICAO address, and the squawk code are stored as integers.
+    /// * Timestamps are stored as whole seconds since the Unix epoch and nanoseconds.
+    /// * The altitude is stored in whole feet.
+    /// * The ground speed, track, vertical speed, and signal strength are stored in hundredths,
+    ///   and the latitude and longitude in units of 0.0000001 degrees.
+    /// * The callsign and the HexIdent text are stored as their lengths in bytes and their
+    ///   text.
+    ///
+    /// Decoding with `from_bytes` produces an equal message, if the values are not more precise
+    /// than the stored units and the text fields are at most 255 bytes long. Longer text is cut
+    /// short. The raw line is not stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64);
+        bytes.push(type_code(&self.message_type));
+        let mut present = 0;
+        let mut mark = |bit: u32, is_some: bool| if is_some { present |= bit };
+        mark(SESSION_ID, self.session_id.is_some());
+        mark(AIRCRAFT_ID, self.aircraft_id.is_some());
+        mark(IDENT, self.ident.is_some());
+        mark(ICAO_ADDRESS, self.icao_address.is_some());
+        mark(FLIGHT_ID, self.flight_id.is_some());
+        #[cfg(feature = "chrono")]
+        {
+            mark(GENERATED, self.generated.is_some());
+            mark(LOGGED, self.logged.is_some());
+        }
+        mark(CALLSIGN, self.callsign.is_some());
+        mark(ALTITUDE, self.altitude.is_some());
+        mark(GROUND_SPEED, self.ground_speed.is_some());
+        mark(TRACK, self.track.is_some());
+        mark(LATITUDE, self.latitude.is_some());
+        mark(LONGITUDE, self.longitude.is_some());
+        mark(VERTICAL_SPEED, self.vertical_speed.is_some());
+        mark(SQUAWK, self.squawk.is_some());
+        mark(RSSI, self.rssi.is_some());
+        mark(EMITTER_CATEGORY, self.emitter_category.is_some());
+        mark(STATUS, self.status.is_some());
+        mark(IDENT_RAW, self.ident_raw.is_some());
+        mark(POSITION_VALID, self.position_valid.is_some());
+        for &(bit, flag) in &[(ALERT, self.alert), (EMERGENCY, self.emergency),
+                              (SPECIAL_POSITION, self.special_position), (ON_GROUND, self.on_ground)] {
+            mark(bit, flag.is_some());
+            mark(bit << FLAG_VALUE_SHIFT, flag == Some(true));
+        }
+        bytes.extend_from_slice(&present.to_le_bytes());
+
+        let ids = [self.session_id, self.aircraft_id, self.ident, self.icao_address, self.flight_id];
+        for id in ids.iter().flatten() {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        #[cfg(feature = "chrono")]
+        for time in [self.generated, self.logged].iter().flatten() {
+            bytes.extend_from_slice(&time.timestamp().to_le_bytes());
+            bytes.extend_from_slice(&time.timestamp_subsec_nanos().to_le_bytes());
+        }
+        if let Some(ref callsign) = self.callsign {
+            push_text(&mut bytes, callsign);
+        }
+        let fixed = [
+            (self.altitude, 1.0),
+            (self.ground_speed, HUNDREDTHS),
+            (self.track, HUNDREDTHS),
+            (self.latitude, DEGREE_UNITS),
+            (self.longitude, DEGREE_UNITS),
+            (self.vertical_speed, HUNDREDTHS),
+        ];
+        for &(value, scale) in fixed.iter() {
+            if let Some(value) = value {
+                bytes.extend_from_slice(&to_fixed(value, scale).to_le_bytes());
+            }
+        }
+        if let Some(squawk) = self.squawk {
+            bytes.extend_from_slice(&squawk.to_le_bytes());
+        }
+        if let Some(rssi) = self.rssi {
+            bytes.extend_from_slice(&to_fixed(rssi, HUNDREDTHS).to_le_bytes());
+        }
+        if let Some(emitter_category) = self.emitter_category {
+            bytes.push(emitter_category);
+        }
+        if let Some(ref status) = self.status {
+            bytes.push(status_code(status));
+        }
+        if let Some(ref ident_raw) = self.ident_raw {
+            push_text(&mut bytes, ident_raw);
+        }
+        if let Some(position_valid) = self.position_valid {
+            bytes.push(position_valid as u8);
+        }
+        bytes
+    }
+
+    /// Decodes a message that was encoded with `to_bytes`
+    ///
+    /// Without the chrono feature, timestamps are skipped.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Message, DecodeError> {
+        let mut reader = Reader { bytes };
+        let message_type = message_type(reader.take::<1>()?[0]).ok_or(DecodeError::InvalidHeader)?;
+        let present = u32::from_le_bytes(reader.take()?);
+        if present & !KNOWN_BITS != 0 {
+            return Err(DecodeError::InvalidHeader);
+        }
+        let has = |bit: u32| present & bit != 0;
+        let mut message = Message::new(message_type);
+
+        for (bit, id) in [
+            (SESSION_ID, &mut message.session_id),
+            (AIRCRAFT_ID, &mut message.aircraft_id),
+            (IDENT, &mut message.ident),
+            (ICAO_ADDRESS, &mut message.icao_address),
+            (FLIGHT_ID, &mut message.flight_id),
+        ] {
+            if has(bit) {
+                *id = Some(u32::from_le_bytes(reader.take()?));
+            }
+        }
+        for &bit in &[GENERATED, LOGGED] {
+            if has(bit) {
+                let seconds = i64::from_le_bytes(reader.take()?);
+                let nanoseconds = u32::from_le_bytes(reader.take()?);
+                #[cfg(feature = "chrono")]
+                {
+                    let time = decode_time(seconds, nanoseconds)?;
+                    if bit == GENERATED {
+                        message.generated = Some(time);
+                    } else {
+                        message.logged = Some(time);
+                    }
+                }
+                #[cfg(not(feature = "chrono"))]
+                let _ = (seconds, nanoseconds);
+            }
+        }
+        if has(CALLSIGN) {
+            message.callsign = Some(reader.take_text(DecodeError::InvalidCallsign)?);
+        }
+        for (bit, value, scale) in [
+            (ALTITUDE, &mut message.altitude, 1.0),
+            (GROUND_SPEED, &mut message.ground_speed, HUNDREDTHS),
+            (TRACK, &mut message.track, HUNDREDTHS),
+            (LATITUDE, &mut message.latitude, DEGREE_UNITS),
+            (LONGITUDE, &mut message.longitude, DEGREE_UNITS),
+            (VERTICAL_SPEED, &mut message.vertical_speed, HUNDREDTHS),
+        ] {
+            if has(bit) {
+                *value = Some(from_fixed(i32::from_le_bytes(reader.take()?), scale));
+            }
+        }
+        if has(SQUAWK) {
+            message.squawk = Some(u16::from_le_bytes(reader.take()?));
+        }
+        for (bit, flag) in [
+            (ALERT, &mut message.alert),
+            (EMERGENCY, &mut message.emergency),
+            (SPECIAL_POSITION, &mut message.special_position),
+            (ON_GROUND, &mut message.on_ground),
+        ] {
+            if has(bit) {
+                *flag = Some(has(bit << FLAG_VALUE_SHIFT));
+            }
+        }
+        if has(RSSI) {
+            message.rssi = Some(from_fixed(i32::from_le_bytes(reader.take()?), HUNDREDTHS));
+        }
+        if has(EMITTER_CATEGORY) {
+            message.emitter_category = Some(reader.take::<1>()?[0]);
+        }
+        if has(STATUS) {
+            let status = status(reader.take::<1>()?[0]).ok_or(DecodeError::InvalidStatus)?;
+            message.status = Some(status);
+        }
+        if has(IDENT_RAW) {
+            message.ident_raw = Some(reader.take_text(DecodeError::InvalidIdent)?);
+        }
+        if has(POSITION_VALID) {
+            message.position_valid = Some(reader.take::<1>()?[0] != 0);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(message)
+    }
+}
+
+/// Reads values from the start of some bytes
+struct Reader<'a> {
+    /// The bytes that have not been read yet
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the provided number of bytes
+    fn take_slice(&mut self, length: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < length {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Reads a fixed number of bytes
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut taken = [0; N];
+        taken.copy_from_slice(self.take_slice(N)?);
+        Ok(taken)
+    }
+
+    /// Reads text that was written with `push_text`, returning the provided error if it is not
+    /// valid UTF-8
+    fn take_text(&mut self, invalid: DecodeError) -> Result<String, DecodeError> {
+        let length = self.take::<1>()?[0] as usize;
+        let text = self.take_slice(length)?;
+        core::str::from_utf8(text).map(String::from).map_err(|_| invalid)
+    }
+}
+
+/// Writes the length of some text and the text, cut short to at most 255 bytes at a character
+/// boundary
+fn push_text(bytes: &mut Vec<u8>, text: &str) {
+    let mut length = text.len().min(u8::MAX as usize);
+    while !text.is_char_boundary(length) {
+        length -= 1;
+    }
+    bytes.push(length as u8);
+    bytes.extend_from_slice(&text.as_bytes()[..length]);
+}
+
+/// Returns the byte that identifies a message type
+fn type_code(message_type: &MessageType) -> u8 {
+    match *message_type {
+        MessageType::SelectionChange => 1,
+        MessageType::NewId => 2,
+        MessageType::NewAircraft => 3,
+        MessageType::StatusAircraft => 4,
+        MessageType::Click => 5,
+        MessageType::Mlat => 6,
+        MessageType::Transmission(ref transmission_type) => 10 + transmission_type.code(),
+    }
+}
+
+/// Returns the message type that a byte identifies
+fn message_type(code: u8) -> Option<MessageType> {
+    match code {
+        1 => Some(MessageType::SelectionChange),
+        2 => Some(MessageType::NewId),
+        3 => Some(MessageType::NewAircraft),
+        4 => Some(MessageType::StatusAircraft),
+        5 => Some(MessageType::Click),
+        6 => Some(MessageType::Mlat),
+        11..=18 => TransmissionType::from_code(code - 10).map(MessageType::Transmission),
+        _ => None,
+    }
+}
+
+/// Returns the byte that identifies an aircraft status
+fn status_code(status: &AircraftStatus) -> u8 {
+    match *status {
+        AircraftStatus::PositionLost => 1,
+        AircraftStatus::SignalLost => 2,
+        AircraftStatus::Remove => 3,
+        AircraftStatus::Delete => 4,
+        AircraftStatus::Ok => 5,
+    }
+}
+
+/// Returns the aircraft status that a byte identifies
+fn status(code: u8) -> Option<AircraftStatus> {
+    match code {
+        1 => Some(AircraftStatus::PositionLost),
+        2 => Some(AircraftStatus::SignalLost),
+        3 => Some(AircraftStatus::Remove),
+        4 => Some(AircraftStatus::Delete),
+        5 => Some(AircraftStatus::Ok),
+        _ => None,
+    }
+}
+
+/// Converts a value to a fixed-point number with the provided number of units per one,
+/// rounding to the nearest unit
+///
+/// Values that do not fit are clamped to the range of an i32.
+fn to_fixed(value: f64, scale: f64) -> i32 {
+    let scaled = value * scale;
+    // The cast rounds towards zero, so the half unit rounds to the nearest unit
+    (if scaled < 0.0 { scaled - 0.5 } else { scaled + 0.5 }) as i32
+}
+
+/// Converts a fixed-point number with the provided number of units per one to a value
+fn from_fixed(fixed: i32, scale: f64) -> f64 {
+    fixed as f64 / scale
+}
+
+/// Converts a Unix timestamp to a local time
+#[cfg(feature = "chrono")]
+fn decode_time(seconds: i64, nanoseconds: u32) -> Result<DateTime<Local>, DecodeError> {
+    if nanoseconds >= 1_000_000_000 {
+        return Err(DecodeError::InvalidTimestamp);
+    }
+    Local.timestamp_opt(seconds, nanoseconds).single().ok_or(DecodeError::InvalidTimestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse;
+
+    /// Checks that a message is the same after encoding and decoding
+    fn assert_round_trip(message: Message) {
+        let bytes = message.to_bytes();
+        assert_eq!(Ok(message), Message::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let message = Message::new(MessageType::Click);
+        assert_eq!(5, message.to_bytes().len());
+        assert_round_trip(message);
+    }
+    #[test]
+    fn test_round_trip_transmissions() {
+        let lines = [
+            "MSG,1,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,DLH123,,,,,,,,,,,",
+            "MSG,3,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,35000,,,\
+             47.65,-122.30825,,,0,0,0,0",
+            "MSG,4,,,A1B2C3,,,,,,,,451.5,123.4,,,-1088,,,,,",
+            "MSG,6,,,A1B2C3,,,,,,,,,,,,,7700,-1,-1,0,",
+            "MSG,2,,,A1B2C3,,,,,,,,12,270,-33.9461,151.1772,,,,,,-1,-30.25,A3",
+            "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,0,,,0",
+            "STA,,5,179,A1B2C3,10103,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,RM,,,,,,,,,,,",
+            "MLAT,,,,A1B2C3,,,,,,,-50,,,,,,,,,,",
+            "MSG,3,,,XYZ-1,,,,,,,35000,,,,,,,,,,",
+        ];
+        for line in lines.iter() {
+            assert_round_trip(parse(line).unwrap());
+        }
+    }
+    #[test]
+    fn test_long_callsign() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsIdentAndCategory));
+        message.callsign = Some("Ü".repeat(200));
+        let decoded = Message::from_bytes(&message.to_bytes()).unwrap();
+        assert_eq!(Some("Ü".repeat(127)), decoded.callsign);
+    }
+    #[test]
+    fn test_decode_errors() {
+        let bytes = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().to_bytes();
+        assert_eq!(Err(DecodeError::UnexpectedEnd), Message::from_bytes(&[]));
+        assert_eq!(Err(DecodeError::UnexpectedEnd), Message::from_bytes(&bytes[..bytes.len() - 1]));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(Err(DecodeError::TrailingBytes), Message::from_bytes(&trailing));
+        let mut bad_type = bytes.clone();
+        bad_type[0] = 19;
+        assert_eq!(Err(DecodeError::InvalidHeader), Message::from_bytes(&bad_type));
+        let mut bad_mask = bytes;
+        bad_mask[4] = 0x80;
+        assert_eq!(Err(DecodeError::InvalidHeader), Message::from_bytes(&bad_mask));
+    }
+}
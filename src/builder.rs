@@ -0,0 +1,212 @@
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(feature = "chrono")]
+use chrono::datetime::DateTime;
+#[cfg(feature = "chrono")]
+use chrono::offset::local::Local;
+use super::{AircraftStatus, Message, MessageType};
+
+/// Builds messages one field at a time
+///
+/// The builder starts with a message that has the provided type and no other fields set.
+pub struct MessageBuilder {
+    /// The message being built
+    message: Message,
+}
+
+impl MessageBuilder {
+    /// Creates a builder for a message of the provided type
+    pub fn new(message_type: MessageType) -> MessageBuilder {
+        MessageBuilder { message: Message::new(message_type) }
+    }
+
+    /// Returns the message that has been built
+    pub fn build(self) -> Message {
+        self.message
+    }
+
+    /// Sets the session ID
+    pub fn session_id(mut self, session_id: u32) -> MessageBuilder {
+        self.message.session_id = Some(session_id);
+        self
+    }
+
+    /// Sets the aircraft ID
+    pub fn aircraft_id(mut self, aircraft_id: u32) -> MessageBuilder {
+        self.message.aircraft_id = Some(aircraft_id);
+        self
+    }
+
+    /// Sets the decimal aircraft identifier
+    pub fn ident(mut self, ident: u32) -> MessageBuilder {
+        self.message.ident = Some(ident);
+        self
+    }
+
+    /// Sets the ICAO address
+    pub fn icao_address(mut self, icao_address: u32) -> MessageBuilder {
+        self.message.icao_address = Some(icao_address);
+        self
+    }
+
+    /// Sets the text of the HexIdent column
+    pub fn ident_raw<S: Into<String>>(mut self, ident_raw: S) -> MessageBuilder {
+        self.message.ident_raw = Some(ident_raw.into());
+        self
+    }
+
+    /// Sets the flight ID
+    pub fn flight_id(mut self, flight_id: u32) -> MessageBuilder {
+        self.message.flight_id = Some(flight_id);
+        self
+    }
+
+    /// Sets the time when the message was generated
+    #[cfg(feature = "chrono")]
+    pub fn generated(mut self, generated: DateTime<Local>) -> MessageBuilder {
+        self.message.generated = Some(generated);
+        self
+    }
+
+    /// Sets the time when the message was logged
+    #[cfg(feature = "chrono")]
+    pub fn logged(mut self, logged: DateTime<Local>) -> MessageBuilder {
+        self.message.logged = Some(logged);
+        self
+    }
+
+    /// Sets the flight number or callsign
+    pub fn callsign<S: Into<String>>(mut self, callsign: S) -> MessageBuilder {
+        self.message.callsign = Some(callsign.into());
+        self
+    }
+
+    /// Sets the altitude, in feet
+    pub fn altitude(mut self, altitude: f64) -> MessageBuilder {
+        self.message.altitude = Some(altitude);
+        self
+    }
+
+    /// Sets the ground speed, in knots
+    pub fn ground_speed(mut self, ground_speed: f64) -> MessageBuilder {
+        self.message.ground_speed = Some(ground_speed);
+        self
+    }
+
+    /// Sets the track, in degrees
+    pub fn track(mut self, track: f64) -> MessageBuilder {
+        self.message.track = Some(track);
+        self
+    }
+
+    /// Sets the latitude
+    pub fn latitude(mut self, latitude: f64) -> MessageBuilder {
+        self.message.latitude = Some(latitude);
+        self
+    }
+
+    /// Sets the longitude
+    pub fn longitude(mut self, longitude: f64) -> MessageBuilder {
+        self.message.longitude = Some(longitude);
+        self
+    }
+
+    /// Sets the vertical speed, in feet per minute
+    pub fn vertical_speed(mut self, vertical_speed: f64) -> MessageBuilder {
+        self.message.vertical_speed = Some(vertical_speed);
+        self
+    }
+
+    /// Sets the transponder code
+    pub fn squawk(mut self, squawk: u16) -> MessageBuilder {
+        self.message.squawk = Some(squawk);
+        self
+    }
+
+    /// Sets the alert flag
+    pub fn alert(mut self, alert: bool) -> MessageBuilder {
+        self.message.alert = Some(alert);
+        self
+    }
+
+    /// Sets the emergency flag
+    pub fn emergency(mut self, emergency: bool) -> MessageBuilder {
+        self.message.emergency = Some(emergency);
+        self
+    }
+
+    /// Sets the Special Position Indicator flag
+    pub fn special_position(mut self, special_position: bool) -> MessageBuilder {
+        self.message.special_position = Some(special_position);
+        self
+    }
+
+    /// Sets the on-ground flag
+    pub fn on_ground(mut self, on_ground: bool) -> MessageBuilder {
+        self.message.on_ground = Some(on_ground);
+        self
+    }
+
+    /// Sets the signal strength
+    pub fn rssi(mut self, rssi: f64) -> MessageBuilder {
+        self.message.rssi = Some(rssi);
+        self
+    }
+
+    /// Sets the emitter category, with the set letter as the high four bits and the category
+    /// number as the low four bits
+    pub fn emitter_category(mut self, emitter_category: u8) -> MessageBuilder {
+        self.message.emitter_category = Some(emitter_category);
+        self
+    }
+
+    /// Sets whether the position is reliable
+    pub fn position_valid(mut self, position_valid: bool) -> MessageBuilder {
+        self.message.position_valid = Some(position_valid);
+        self
+    }
+
+    /// Sets the status of the aircraft
+    pub fn status(mut self, status: AircraftStatus) -> MessageBuilder {
+        self.message.status = Some(status);
+        self
+    }
+
+    /// Sets the line that the message was parsed from
+    pub fn raw<S: Into<String>>(mut self, raw: S) -> MessageBuilder {
+        self.message.raw = Some(raw.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::TransmissionType;
+
+    #[test]
+    fn test_build_empty() {
+        let message = MessageBuilder::new(MessageType::Click).build();
+        assert_eq!(Message::new(MessageType::Click), message);
+    }
+    #[test]
+    fn test_build_transmission() {
+        let message_type = MessageType::Transmission(TransmissionType::SurveillanceId);
+        let built = MessageBuilder::new(message_type.clone())
+            .icao_address(0x3C6586)
+            .callsign("DLH123")
+            .altitude(35000.0)
+            .squawk(1000)
+            .on_ground(false)
+            .build();
+
+        let mut expected = Message::new(message_type);
+        expected.icao_address = Some(0x3C6586);
+        expected.callsign = Some(String::from("DLH123"));
+        expected.altitude = Some(35000.0);
+        expected.squawk = Some(1000);
+        expected.on_ground = Some(false);
+        assert_eq!(expected, built);
+    }
+}
@@ -0,0 +1,52 @@
+
+use chrono::datetime::DateTime;
+use chrono::offset::local::Local;
+
+/// A source of the current time
+///
+/// Time-dependent helpers such as `Message::is_stale` take a clock, so that they can be tested
+/// with a fixed time.
+pub trait Clock {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// A clock that reads the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock that always returns the same time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Local>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::offset::TimeZone;
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let time = Local.ymd(2016, 3, 11).and_hms(21, 24, 53);
+        let clock = FixedClock(time);
+        assert_eq!(time, clock.now());
+        assert_eq!(time, clock.now());
+    }
+    #[test]
+    fn test_system_clock() {
+        let before = Local::now();
+        let now = SystemClock.now();
+        assert!(before <= now && now <= Local::now());
+    }
+}
@@ -0,0 +1,100 @@
+
+use std::collections::HashMap;
+use chrono::Duration;
+use chrono::datetime::DateTime;
+use chrono::offset::local::Local;
+use super::Message;
+
+/// Suppresses position reports that repeat a recent report from the same aircraft
+///
+/// A position report is a duplicate if an earlier accepted report from the same ICAO address
+/// was generated no more than the window before it, and its latitude and longitude are both
+/// within the tolerance of the earlier ones. Duplicates are not remembered, so an aircraft that
+/// stays in one place is reported again once the window has passed since the last accepted
+/// report.
+///
+/// Messages without a position, an ICAO address, or a generated time are always accepted.
+#[derive(Debug, Clone)]
+pub struct Deduplicator {
+    /// How long after a report the same position counts as a duplicate
+    window: Duration,
+    /// The largest difference in latitude or longitude, in degrees, between duplicates
+    tolerance: f64,
+    /// The generated time and position of the latest accepted report from each aircraft
+    latest: HashMap<u32, (DateTime<Local>, (f64, f64))>,
+}
+
+impl Deduplicator {
+    /// Creates a deduplicator with the provided window and tolerance in degrees
+    pub fn new(window: Duration, tolerance: f64) -> Deduplicator {
+        Deduplicator {
+            window,
+            tolerance,
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Returns the message, or None if it is a duplicate of an earlier report
+    pub fn accept(&mut self, message: Message) -> Option<Message> {
+        let (icao_address, generated, position) =
+            match (message.icao_address, message.generated, message.position()) {
+                (Some(icao_address), Some(generated), Some(position)) => {
+                    (icao_address, generated, position)
+                }
+                _ => return Some(message),
+            };
+        if let Some(&(previous_generated, (latitude, longitude))) = self.latest.get(&icao_address) {
+            let elapsed = generated - previous_generated;
+            let duplicate = elapsed >= Duration::zero() && elapsed <= self.window &&
+                (position.0 - latitude).abs() <= self.tolerance &&
+                (position.1 - longitude).abs() <= self.tolerance;
+            if duplicate {
+                return None;
+            }
+        }
+        self.latest.insert(icao_address, (generated, position));
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::offset::TimeZone;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    /// Creates a position message from an aircraft, generated at the provided second
+    fn position(icao_address: u32, second: u32, latitude: f64, longitude: f64) -> Message {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.icao_address = Some(icao_address);
+        message.generated = Some(Local.ymd(2016, 3, 11).and_hms(21, 24, second));
+        message.latitude = Some(latitude);
+        message.longitude = Some(longitude);
+        message
+    }
+
+    #[test]
+    fn test_duplicate_within_window() {
+        let mut deduplicator = Deduplicator::new(Duration::seconds(5), 0.001);
+        let first = position(0xA1B2C3, 0, 47.65, -122.30825);
+        assert_eq!(Some(first.clone()), deduplicator.accept(first));
+        assert_eq!(None, deduplicator.accept(position(0xA1B2C3, 2, 47.6505, -122.3085)));
+        // Another aircraft at the same position is not a duplicate
+        let other = position(0x3C6586, 2, 47.65, -122.30825);
+        assert_eq!(Some(other.clone()), deduplicator.accept(other));
+        // A message without a position is never a duplicate
+        let mut velocity = Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel));
+        velocity.icao_address = Some(0xA1B2C3);
+        assert_eq!(Some(velocity.clone()), deduplicator.accept(velocity));
+    }
+    #[test]
+    fn test_distinct_reports() {
+        let mut deduplicator = Deduplicator::new(Duration::seconds(5), 0.001);
+        assert!(deduplicator.accept(position(0xA1B2C3, 0, 47.65, -122.30825)).is_some());
+        // Moved further than the tolerance
+        assert!(deduplicator.accept(position(0xA1B2C3, 1, 47.66, -122.30825)).is_some());
+        // The same position after the window
+        assert!(deduplicator.accept(position(0xA1B2C3, 7, 47.66, -122.30825)).is_some());
+        assert!(deduplicator.accept(position(0xA1B2C3, 8, 47.66, -122.30825)).is_none());
+    }
+}
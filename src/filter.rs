@@ -0,0 +1,100 @@
+
+use std::collections::HashSet;
+use super::{Message, MessageType};
+
+/// Selects the messages that a consumer is interested in
+///
+/// Each criterion that is set must match for a message to be selected. A filter with no
+/// criteria selects every message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    /// If this is set, only messages of these types are selected
+    pub message_types: Option<HashSet<MessageType>>,
+    /// If this is set, only messages from these ICAO addresses are selected
+    pub icao_addresses: Option<HashSet<u32>>,
+    /// If this is set, only messages with a position in this box are selected
+    ///
+    /// The box is (minimum latitude, minimum longitude, maximum latitude, maximum longitude),
+    /// in degrees. The edges are inside the box.
+    pub bounding_box: Option<(f64, f64, f64, f64)>,
+}
+
+impl MessageFilter {
+    /// Creates a filter that selects every message
+    pub fn new() -> MessageFilter {
+        MessageFilter::default()
+    }
+
+    /// Returns true if the message meets all the criteria of this filter
+    pub fn matches(&self, message: &Message) -> bool {
+        if let Some(ref message_types) = self.message_types {
+            if !message_types.contains(&message.message_type) {
+                return false;
+            }
+        }
+        if let Some(ref icao_addresses) = self.icao_addresses {
+            if !message.icao_address.is_some_and(|icao_address| icao_addresses.contains(&icao_address)) {
+                return false;
+            }
+        }
+        if let Some((min_latitude, min_longitude, max_latitude, max_longitude)) = self.bounding_box {
+            match message.position() {
+                Some((latitude, longitude)) => {
+                    if !(min_latitude..=max_latitude).contains(&latitude) ||
+                        !(min_longitude..=max_longitude).contains(&longitude) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse, TransmissionType};
+
+    #[test]
+    fn test_no_criteria() {
+        let filter = MessageFilter::new();
+        assert!(filter.matches(&Message::new(MessageType::Click)));
+    }
+    #[test]
+    fn test_message_types() {
+        let filter = MessageFilter {
+            message_types: Some(vec![
+                MessageType::NewAircraft,
+                MessageType::Transmission(TransmissionType::EsAirbornePos),
+            ].into_iter().collect()),
+            ..MessageFilter::default()
+        };
+        assert!(filter.matches(&Message::new(MessageType::NewAircraft)));
+        assert!(filter.matches(&Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos))));
+        assert!(!filter.matches(&Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel))));
+        assert!(!filter.matches(&Message::new(MessageType::SelectionChange)));
+    }
+    #[test]
+    fn test_icao_addresses() {
+        let filter = MessageFilter {
+            icao_addresses: Some(vec![0xA1B2C3].into_iter().collect()),
+            ..MessageFilter::default()
+        };
+        assert!(filter.matches(&parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap()));
+        assert!(!filter.matches(&parse("MSG,3,,,3C6586,,,,,,,35000,,,,,,,,,,").unwrap()));
+        assert!(!filter.matches(&parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,").unwrap()));
+    }
+    #[test]
+    fn test_bounding_box() {
+        let filter = MessageFilter {
+            bounding_box: Some((47.0, -123.0, 48.0, -122.0)),
+            ..MessageFilter::default()
+        };
+        assert!(filter.matches(&parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap()));
+        assert!(filter.matches(&parse("MSG,3,,,A1B2C3,,,,,,,35000,,,48,-122,,,,,,").unwrap()));
+        assert!(!filter.matches(&parse("MSG,3,,,A1B2C3,,,,,,,35000,,,51.47,-0.45,,,,,,").unwrap()));
+        assert!(!filter.matches(&parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,,,,,,,").unwrap()));
+    }
+}
@@ -0,0 +1,169 @@
+
+#[cfg(not(feature = "std"))]
+use alloc::borrow::ToOwned;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(feature = "chrono")]
+use chrono::datetime::DateTime;
+#[cfg(feature = "chrono")]
+use chrono::offset::local::Local;
+#[cfg(feature = "chrono")]
+use chrono::offset::TimeZone;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "serde", feature = "chrono"))]
+use super::serde_support;
+use super::{Message, MessageType};
+
+/// The values that `Message::into_flat` uses for fields that are None
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatDefaults {
+    /// The value for the session, aircraft, and flight IDs, the ident, and the ICAO address
+    pub id: u32,
+    /// The value for the altitude, speeds, track, position, and signal strength
+    pub number: f64,
+    /// The value for the squawk code
+    pub squawk: u16,
+    /// The value for the emitter category
+    pub emitter_category: u8,
+    /// The value for the flags
+    pub flag: bool,
+    /// The value for the text fields
+    pub text: String,
+    /// The value for the generated and logged times
+    #[cfg(feature = "chrono")]
+    pub time: DateTime<Local>,
+}
+
+impl Default for FlatDefaults {
+    /// Returns defaults of zero, false, empty text, and the Unix epoch
+    fn default() -> FlatDefaults {
+        FlatDefaults {
+            id: 0,
+            number: 0.0,
+            squawk: 0,
+            emitter_category: 0,
+            flag: false,
+            text: String::new(),
+            #[cfg(feature = "chrono")]
+            time: Local.timestamp(0, 0),
+        }
+    }
+}
+
+/// A message with a value for every field, for storage that does not support missing values
+///
+/// The fields are the same as those of `Message`, and the status is stored as its two-letter
+/// code.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FlatMessage {
+    pub message_type: MessageType,
+    pub session_id: u32,
+    pub aircraft_id: u32,
+    pub ident: u32,
+    pub icao_address: u32,
+    pub ident_raw: String,
+    pub flight_id: u32,
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::required_date_time"))]
+    pub generated: DateTime<Local>,
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::required_date_time"))]
+    pub logged: DateTime<Local>,
+    pub callsign: String,
+    pub altitude: f64,
+    pub ground_speed: f64,
+    pub track: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub vertical_speed: f64,
+    pub squawk: u16,
+    pub alert: bool,
+    pub emergency: bool,
+    pub special_position: bool,
+    pub on_ground: bool,
+    pub rssi: f64,
+    pub emitter_category: u8,
+    pub position_valid: bool,
+    pub raw: String,
+    pub status: String,
+}
+
+impl Message {
+    /// Converts this message into a flat message, replacing fields that are None with the
+    /// provided defaults
+    pub fn into_flat(self, defaults: FlatDefaults) -> FlatMessage {
+        let text = |value: Option<String>| value.unwrap_or_else(|| defaults.text.clone());
+        FlatMessage {
+            message_type: self.message_type,
+            session_id: self.session_id.unwrap_or(defaults.id),
+            aircraft_id: self.aircraft_id.unwrap_or(defaults.id),
+            ident: self.ident.unwrap_or(defaults.id),
+            icao_address: self.icao_address.unwrap_or(defaults.id),
+            ident_raw: text(self.ident_raw),
+            flight_id: self.flight_id.unwrap_or(defaults.id),
+            #[cfg(feature = "chrono")]
+            generated: self.generated.unwrap_or(defaults.time),
+            #[cfg(feature = "chrono")]
+            logged: self.logged.unwrap_or(defaults.time),
+            callsign: text(self.callsign),
+            altitude: self.altitude.unwrap_or(defaults.number),
+            ground_speed: self.ground_speed.unwrap_or(defaults.number),
+            track: self.track.unwrap_or(defaults.number),
+            latitude: self.latitude.unwrap_or(defaults.number),
+            longitude: self.longitude.unwrap_or(defaults.number),
+            vertical_speed: self.vertical_speed.unwrap_or(defaults.number),
+            squawk: self.squawk.unwrap_or(defaults.squawk),
+            alert: self.alert.unwrap_or(defaults.flag),
+            emergency: self.emergency.unwrap_or(defaults.flag),
+            special_position: self.special_position.unwrap_or(defaults.flag),
+            on_ground: self.on_ground.unwrap_or(defaults.flag),
+            rssi: self.rssi.unwrap_or(defaults.number),
+            emitter_category: self.emitter_category.unwrap_or(defaults.emitter_category),
+            position_valid: self.position_valid.unwrap_or(defaults.flag),
+            raw: text(self.raw),
+            status: text(self.status.map(|status| status.code().to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse, TransmissionType};
+
+    #[test]
+    fn test_default_values() {
+        let flat = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos))
+            .into_flat(FlatDefaults::default());
+        assert_eq!(0, flat.icao_address);
+        assert_eq!(0.0, flat.altitude);
+        assert_eq!(0, flat.squawk);
+        assert!(!flat.on_ground);
+        assert_eq!("", flat.callsign);
+        assert_eq!("", flat.status);
+        #[cfg(feature = "chrono")]
+        assert_eq!(0, flat.generated.timestamp());
+    }
+    #[test]
+    fn test_custom_defaults() {
+        let defaults = FlatDefaults {
+            number: -1.0,
+            text: String::from("?"),
+            flag: true,
+            ..FlatDefaults::default()
+        };
+        let message = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,0").unwrap();
+        let flat = message.into_flat(defaults);
+        assert_eq!(0xA1B2C3, flat.icao_address);
+        assert_eq!(String::from("A1B2C3"), flat.ident_raw);
+        assert_eq!(35000.0, flat.altitude);
+        assert_eq!(47.65, flat.latitude);
+        assert_eq!(-1.0, flat.ground_speed);
+        assert_eq!(-1.0, flat.rssi);
+        assert_eq!("?", flat.callsign);
+        assert!(!flat.on_ground);
+        assert!(flat.alert);
+    }
+}
@@ -0,0 +1,45 @@
+
+use std::io::{self, Write};
+use super::Message;
+
+/// Writes messages as JSON lines, with one JSON object for each message on its own line
+///
+/// The objects have the same form as the serde serialization of `Message`, so each line can be
+/// read back with `serde_json::from_str`.
+pub fn write_jsonl<W: Write>(messages: &[Message], writer: &mut W) -> io::Result<()> {
+    for message in messages {
+        serde_json::to_writer(&mut *writer, message)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use super::*;
+    use super::super::parse;
+
+    #[test]
+    fn test_write_and_read() {
+        let messages = vec![
+            parse("MSG,3,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,\
+                   ,35000,,,47.65,-122.30825,,,0,0,0,0").unwrap(),
+            parse("MSG,1,,,3C6586,,,,,,DLH123,,,,,,,,,,,").unwrap(),
+        ];
+        let mut output = Vec::new();
+        write_jsonl(&messages, &mut output).unwrap();
+        let lines = output.lines().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(2, lines.len());
+        let read = lines.iter()
+            .map(|line| serde_json::from_str::<Message>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, read);
+    }
+    #[test]
+    fn test_write_empty() {
+        let mut output = Vec::new();
+        write_jsonl(&[], &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+}
@@ -1,20 +1,207 @@
+//! Parsing of the SBS-1 (BaseStation) text format for ADS-B data
+//!
+//! The default `std` and `chrono` features can be disabled to use this crate with only
+//! `core` and `alloc`. Without `chrono`, messages do not have timestamps. Without `std`,
+//! the helpers that need the standard library, such as `MessageReader` and the geographic
+//! calculations, are not available.
+//!
+//! Message types can also be parsed on their own, from the same tokens that start a line,
+//! with `MessageType::try_from`. A transmission is only a complete message type together with
+//! its transmission type, so its token is the first two fields of the line, such as "MSG,3".
+//! "MSG" alone cannot be converted, and is a `ParseError::InvalidTransmissionType`. Use
+//! `TransmissionType::try_from` to check the second field on its own.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(feature = "chrono")]
 extern crate chrono;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(all(test, feature = "tokio"))]
+extern crate futures;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(not(feature = "std"))]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+use core::fmt::Display;
+use core::ops::Range;
+use core::str::FromStr;
+#[cfg(feature = "chrono")]
 use chrono::offset::local::Local;
-use chrono::offset::TimeZone;
+#[cfg(feature = "chrono")]
 use chrono::datetime::DateTime;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "chrono")]
+mod aggregator;
+mod binary;
+mod builder;
+#[cfg(feature = "chrono")]
+mod clock;
+#[cfg(feature = "chrono")]
+mod dedup;
+#[cfg(feature = "std")]
+mod filter;
+mod flat;
+mod icao;
+#[cfg(feature = "serde")]
+mod jsonl;
+mod parser;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "chrono")]
+mod replay;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod sort;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "tokio")]
+mod stream;
+mod streaming;
+#[cfg(feature = "chrono")]
+mod timestamp;
+
+#[cfg(feature = "chrono")]
+pub use aggregator::{SquawkChange, TrackAggregator};
+pub use binary::DecodeError;
+pub use builder::MessageBuilder;
+#[cfg(feature = "serde")]
+pub use jsonl::write_jsonl;
+pub use parser::Parser;
+#[cfg(feature = "chrono")]
+pub use clock::{Clock, FixedClock, SystemClock};
+#[cfg(feature = "chrono")]
+pub use dedup::Deduplicator;
+#[cfg(feature = "std")]
+pub use filter::MessageFilter;
+pub use flat::{FlatDefaults, FlatMessage};
+#[cfg(feature = "std")]
+pub use reader::MessageReader;
+#[cfg(feature = "flate2")]
+pub use reader::{open_sbs1_gz, read_sbs1_gz};
+#[cfg(feature = "chrono")]
+pub use replay::Replayer;
+#[cfg(feature = "chrono")]
+pub use sort::sort_by_time;
+pub use sort::sort_by_aircraft;
+#[cfg(feature = "std")]
+pub use stats::FeedStats;
+#[cfg(feature = "tokio")]
+pub use stream::MessageStream;
+pub use streaming::StreamingParser;
+#[cfg(feature = "chrono")]
+use timestamp::{format_date_time, parse_timestamp};
+// The tests use parse_date_time from the crate root, where it was before the timestamp module
+#[cfg(all(test, feature = "chrono"))]
+use timestamp::parse_date_time;
+#[cfg(feature = "chrono")]
+pub use timestamp::{parse_detailed, DetailedMessage, TimestampError};
+
+/// The number of meters in one foot
+const METERS_PER_FOOT: f64 = 0.3048;
+/// The number of kilometers per hour in one knot
+const KMH_PER_KNOT: f64 = 1.852;
+/// The number of meters per second in one knot
+const MS_PER_KNOT: f64 = 0.514444;
+/// The number of meters per second in one foot per minute
+const MS_PER_FOOT_PER_MINUTE: f64 = 0.00508;
+
+/// The mean radius of the Earth, in kilometers
+#[cfg(feature = "std")]
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// The standard sea-level pressure, in hectopascals, that reported altitudes are based on
+const STANDARD_PRESSURE_HPA: f64 = 1013.25;
+/// The approximate change in altitude, in feet, for a change in pressure of one hectopascal
+/// near sea level
+const FEET_PER_HPA: f64 = 27.0;
+
+/// The largest vertical speed, in feet per minute, that counts as level flight
+///
+/// This is one step of the 64 feet per minute resolution that ADS-B uses for vertical rates.
+const LEVEL_VERTICAL_SPEED: f64 = 64.0;
+
+/// The lowest altitude, in feet, that a valid message can report
+const MIN_ALTITUDE: f64 = -1500.0;
+/// The highest altitude, in feet, that a valid message can report
+const MAX_ALTITUDE: f64 = 100000.0;
 
-/// The expected format for combined times and dates
-const DATE_TIME_FORMAT: &'static str = "%Y/%m/%d %H:%M:%S%.f";
+/// The number of fields in a standard line
+const FIELD_COUNT: usize = 22;
+/// The number of fields in a line with all the extra fields that some feeds append: the signal
+/// strength, the emitter category, and the position validity flag
+const EXTENDED_FIELD_COUNT: usize = 25;
+/// The numbers of fields that a line can have, unless the parse options accept other numbers
+const DEFAULT_EXPECTED_FIELDS: Range<usize> = FIELD_COUNT..EXTENDED_FIELD_COUNT + 1;
+/// The character that separates the fields in a standard line
+const DEFAULT_DELIMITER: char = ',';
+
+/// The logical name of each field, used in error reports
+const FIELD_NAMES: [&str; EXTENDED_FIELD_COUNT] = [
+    "message_type",
+    "transmission_type",
+    "session_id",
+    "aircraft_id",
+    "ident",
+    "flight_id",
+    "generated_date",
+    "generated_time",
+    "logged_date",
+    "logged_time",
+    "callsign",
+    "altitude",
+    "ground_speed",
+    "track",
+    "latitude",
+    "longitude",
+    "vertical_speed",
+    "squawk",
+    "alert",
+    "emergency",
+    "special_position",
+    "on_ground",
+    "rssi",
+    "emitter_category",
+    "position_valid",
+];
 
 /// Types of messages
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MessageType {
     SelectionChange,
     NewId,
     NewAircraft,
     StatusAircraft,
     Click,
+    /// Indicates a position that a receiver network, such as the one that piaware feeds,
+    /// computed by multilateration from the times that several receivers received signals
+    /// from the aircraft
+    ///
+    /// The fields are the same as in a transmission. The transmission type of the line is not
+    /// kept.
+    Mlat,
     /// Indicates a transmission from an aircraft. This type is most common.
     ///
     /// The type of the transmission is included.
@@ -22,7 +209,8 @@ pub enum MessageType {
 }
 
 /// Types of transmissions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransmissionType {
     EsIdentAndCategory,
     EsSurfacePos,
@@ -34,26 +222,303 @@ pub enum TransmissionType {
     AllCallReply,
 }
 
+impl MessageType {
+    /// Returns every message type except transmissions, which are listed by
+    /// `TransmissionType::all`
+    pub fn all_base() -> &'static [MessageType] {
+        &[
+            MessageType::SelectionChange,
+            MessageType::NewId,
+            MessageType::NewAircraft,
+            MessageType::StatusAircraft,
+            MessageType::Click,
+            MessageType::Mlat,
+        ]
+    }
+
+    /// Returns the token in the first field of a line for this message type, such as SEL or MSG
+    pub fn type_token(&self) -> &'static str {
+        match *self {
+            MessageType::SelectionChange => "SEL",
+            MessageType::NewId => "ID",
+            MessageType::NewAircraft => "AIR",
+            MessageType::StatusAircraft => "STA",
+            MessageType::Click => "CLK",
+            MessageType::Mlat => "MLAT",
+            MessageType::Transmission(_) => "MSG",
+        }
+    }
+
+    /// Returns true if this is a transmission from an aircraft
+    pub fn is_transmission(&self) -> bool {
+        self.transmission_type().is_some()
+    }
+
+    /// Returns the type of the transmission, if this is a transmission from an aircraft
+    pub fn transmission_type(&self) -> Option<&TransmissionType> {
+        match *self {
+            MessageType::Transmission(ref transmission_type) => Some(transmission_type),
+            _ => None,
+        }
+    }
+}
+
+impl TransmissionType {
+    /// Returns every transmission type, in order of their codes
+    pub fn all() -> &'static [TransmissionType] {
+        &[
+            TransmissionType::EsIdentAndCategory,
+            TransmissionType::EsSurfacePos,
+            TransmissionType::EsAirbornePos,
+            TransmissionType::EsAirborneVel,
+            TransmissionType::SurveillanceAlt,
+            TransmissionType::SurveillanceId,
+            TransmissionType::AirToAir,
+            TransmissionType::AllCallReply,
+        ]
+    }
+
+    /// Returns the SBS-1 code for this transmission type, from 1 to 8
+    pub fn code(&self) -> u8 {
+        match *self {
+            TransmissionType::EsIdentAndCategory => 1,
+            TransmissionType::EsSurfacePos => 2,
+            TransmissionType::EsAirbornePos => 3,
+            TransmissionType::EsAirborneVel => 4,
+            TransmissionType::SurveillanceAlt => 5,
+            TransmissionType::SurveillanceId => 6,
+            TransmissionType::AirToAir => 7,
+            TransmissionType::AllCallReply => 8,
+        }
+    }
+
+    /// Returns the names of the fields of `Message` that transmissions of this type usually
+    /// carry, apart from the identifiers and timestamps that every message can have
+    ///
+    /// For example, ES identification transmissions carry a callsign and airborne position
+    /// transmissions carry an altitude and a position.
+    pub fn expected_fields(&self) -> &'static [&'static str] {
+        match *self {
+            TransmissionType::EsIdentAndCategory => &["callsign"],
+            TransmissionType::EsSurfacePos => {
+                &["altitude", "ground_speed", "track", "latitude", "longitude", "on_ground"]
+            }
+            TransmissionType::EsAirbornePos => {
+                &["altitude", "latitude", "longitude", "alert", "emergency", "special_position",
+                  "on_ground"]
+            }
+            TransmissionType::EsAirborneVel => &["ground_speed", "track", "vertical_speed"],
+            TransmissionType::SurveillanceAlt => {
+                &["altitude", "alert", "special_position", "on_ground"]
+            }
+            TransmissionType::SurveillanceId => {
+                &["altitude", "squawk", "alert", "emergency", "special_position", "on_ground"]
+            }
+            TransmissionType::AirToAir => &["altitude", "on_ground"],
+            TransmissionType::AllCallReply => &["on_ground"],
+        }
+    }
+
+    /// Returns the transmission type with the provided SBS-1 code, or None if the code is not
+    /// valid
+    pub fn from_code(code: u8) -> Option<TransmissionType> {
+        match code {
+            1 => Some(TransmissionType::EsIdentAndCategory),
+            2 => Some(TransmissionType::EsSurfacePos),
+            3 => Some(TransmissionType::EsAirbornePos),
+            4 => Some(TransmissionType::EsAirborneVel),
+            5 => Some(TransmissionType::SurveillanceAlt),
+            6 => Some(TransmissionType::SurveillanceId),
+            7 => Some(TransmissionType::AirToAir),
+            8 => Some(TransmissionType::AllCallReply),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for MessageType {
+    type Error = ParseError;
+
+    /// Parses a message type token, such as "SEL"
+    ///
+    /// A transmission needs its transmission type as well, so the token for a transmission is
+    /// the first two fields of its line, such as "MSG,3". "MSG" alone is a
+    /// `ParseError::InvalidTransmissionType`.
+    fn try_from(token: &'a str) -> Result<MessageType, ParseError> {
+        let mut fields = token.splitn(2, ',');
+        let message_type = fields.next().unwrap_or_default();
+        let transmission_type = fields.next().unwrap_or_default();
+        if message_type.trim() != "MSG" && !transmission_type.trim().is_empty() {
+            return Err(ParseError::InvalidMessageType);
+        }
+        parse_message_type(message_type, transmission_type)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for TransmissionType {
+    type Error = ParseError;
+
+    /// Parses a transmission type token, which is the SBS-1 code from 1 to 8
+    ///
+    /// The token may be surrounded by whitespace, but must otherwise only have digits, so a
+    /// signed code such as +3 is invalid.
+    fn try_from(token: &'a str) -> Result<TransmissionType, ParseError> {
+        let token = token.trim();
+        if !token.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError::InvalidTransmissionType);
+        }
+        token.parse().ok()
+            .and_then(TransmissionType::from_code)
+            .ok_or(ParseError::InvalidTransmissionType)
+    }
+}
+
+impl Display for MessageType {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            MessageType::SelectionChange => write!(f, "Selection Change"),
+            MessageType::NewId => write!(f, "New ID"),
+            MessageType::NewAircraft => write!(f, "New Aircraft"),
+            MessageType::StatusAircraft => write!(f, "Aircraft Status"),
+            MessageType::Click => write!(f, "Click"),
+            MessageType::Mlat => write!(f, "Multilateration"),
+            MessageType::Transmission(ref transmission_type) => {
+                write!(f, "Transmission ({})", transmission_type)
+            }
+        }
+    }
+}
+
+impl Display for TransmissionType {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let name = match *self {
+            TransmissionType::EsIdentAndCategory => "ES Identification and Category",
+            TransmissionType::EsSurfacePos => "ES Surface Position",
+            TransmissionType::EsAirbornePos => "ES Airborne Position",
+            TransmissionType::EsAirborneVel => "ES Airborne Velocity",
+            TransmissionType::SurveillanceAlt => "Surveillance Altitude",
+            TransmissionType::SurveillanceId => "Surveillance ID",
+            TransmissionType::AirToAir => "Air To Air",
+            TransmissionType::AllCallReply => "All Call Reply",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The status of an aircraft, reported by a StatusAircraft (STA) message
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AircraftStatus {
+    /// PL, position lost: no position has been received from the aircraft recently
+    PositionLost,
+    /// SL, signal lost: no messages have been received from the aircraft recently
+    SignalLost,
+    /// RM, remove: the aircraft has been removed from the display
+    Remove,
+    /// AD, delete: the aircraft has been deleted from the list of aircraft
+    Delete,
+    /// OK: the aircraft is being received normally again
+    Ok,
+}
+
+impl AircraftStatus {
+    /// Returns the two-letter SBS-1 code for this status
+    pub fn code(&self) -> &'static str {
+        match *self {
+            AircraftStatus::PositionLost => "PL",
+            AircraftStatus::SignalLost => "SL",
+            AircraftStatus::Remove => "RM",
+            AircraftStatus::Delete => "AD",
+            AircraftStatus::Ok => "OK",
+        }
+    }
+
+    /// Returns the status with the provided SBS-1 code, or None if the code is not known
+    pub fn from_code(code: &str) -> Option<AircraftStatus> {
+        match code {
+            "PL" => Some(AircraftStatus::PositionLost),
+            "SL" => Some(AircraftStatus::SignalLost),
+            "RM" => Some(AircraftStatus::Remove),
+            "AD" => Some(AircraftStatus::Delete),
+            "OK" => Some(AircraftStatus::Ok),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an aircraft is climbing, descending, or flying level
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClimbState {
+    /// The vertical speed is more than 64 feet per minute upwards
+    Climbing,
+    /// The vertical speed is more than 64 feet per minute downwards
+    Descending,
+    /// The vertical speed is 64 feet per minute or less
+    Level,
+}
+
+/// Whether a position was reported by an aircraft on the ground or in the air
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionKind {
+    /// The aircraft is on the ground
+    Surface,
+    /// The aircraft is in the air
+    Airborne,
+}
+
+/// Types of emergencies that an aircraft can indicate with its squawk code
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmergencyKind {
+    /// Squawk 7500, unlawful interference
+    Hijack,
+    /// Squawk 7600, loss of radio communication
+    RadioFailure,
+    /// Squawk 7700, general emergency
+    GeneralEmergency,
+}
+
 /// An SBS-1 message
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Message {
     /// The type of the message
     pub message_type: MessageType,
     pub session_id: Option<u32>,
     pub aircraft_id: Option<u32>,
-    /// Aircraft identifier
+    /// Aircraft identifier, parsed as a decimal number
+    ///
+    /// The HexIdent column actually contains the hexadecimal ICAO address of the aircraft,
+    /// so this is None for most real aircraft. It is kept for compatibility; use
+    /// `icao_address` instead.
     pub ident: Option<u32>,
+    /// The 24-bit ICAO address of the aircraft, parsed from the hexadecimal HexIdent column
+    pub icao_address: Option<u32>,
+    /// The text of the HexIdent column, without surrounding whitespace
+    ///
+    /// Some feeds put identifiers in this column that are not numbers, which are kept here
+    /// even though `ident` and `icao_address` are None.
+    pub ident_raw: Option<String>,
     pub flight_id: Option<u32>,
     /// When the message was generated
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::date_time"))]
     pub generated: Option<DateTime<Local>>,
     /// When the message was logged
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::date_time"))]
     pub logged: Option<DateTime<Local>>,
-    /// The flight number or callsign
+    /// The flight number or callsign, without any padding
+    ///
+    /// In a StatusAircraft message, this column holds the status code instead. The code is
+    /// also available, decoded, as `status`.
     pub callsign: Option<String>,
-    /// The altitude of the aircraft above mean sea level, assuming an altimeter setting of
-    /// 1013 millibars (29.92 inches of mercury)
+    /// The altitude of the aircraft above mean sea level in feet, assuming an altimeter setting
+    /// of 1013 millibars (29.92 inches of mercury)
+    ///
+    /// This can be slightly negative, for an aircraft at a low airport when the air pressure is
+    /// high. `validate` accepts altitudes down to -1500 feet.
     pub altitude: Option<f64>,
-    /// The ground speed of the aircraft, in some unknown unit
+    /// The ground speed of the aircraft, in knots
     pub ground_speed: Option<f64>,
     /// The track of the aircraft, in degrees
     pub track: Option<f64>,
@@ -61,9 +526,12 @@ pub struct Message {
     pub latitude: Option<f64>,
     /// The aircraft longitude
     pub longitude: Option<f64>,
-    /// The vertical speed of the aircraft, in some unknown unit (possibly feet per minute)
+    /// The vertical speed of the aircraft, in feet per minute
     pub vertical_speed: Option<f64>,
     /// The current transponder code
+    ///
+    /// Transponder codes are four octal digits. SBS-1 writes those digits as a decimal number,
+    /// so the code 1200 has the value 1200 here, not the octal value 0o1200.
     pub squawk: Option<u16>,
     /// Indicates the transponder code has changed
     pub alert: Option<bool>,
@@ -73,18 +541,61 @@ pub struct Message {
     pub special_position: Option<bool>,
     /// Indicates the aircraft is on the ground
     pub on_ground: Option<bool>,
+    /// The received signal strength, from the extra field that some receivers append
+    pub rssi: Option<f64>,
+    /// The ADS-B emitter category, from the extra field that some feeds append after the
+    /// signal strength
+    ///
+    /// Aircraft send their category in ES identification and category transmissions. The
+    /// category is written as a set letter and a number, such as A3, and is stored with the set
+    /// as the high four bits and the number as the low four bits, so A3 is 0xA3. The sets are:
+    ///
+    /// * A: aircraft by weight class. A1 is light (under 15,500 lb), A2 small, A3 large, A4
+    ///   high vortex large (such as a Boeing 757), A5 heavy (over 300,000 lb), A6 high
+    ///   performance, and A7 rotorcraft.
+    /// * B: other flying objects. B1 is a glider or sailplane, B2 lighter than air, B3
+    ///   parachutist or skydiver, B4 ultralight or paraglider, B6 unmanned aerial vehicle, and B7
+    ///   space or trans-atmospheric vehicle.
+    /// * C: surface vehicles and obstacles. C1 is an emergency vehicle, C2 a service vehicle,
+    ///   and C3 to C7 are obstacles.
+    /// * D: reserved.
+    ///
+    /// In every set, 0 means that no category information is available, and the numbers that
+    /// are not listed are reserved.
+    pub emitter_category: Option<u8>,
+    /// Whether the receiver considers the position reliable, from an extra column that some
+    /// feeds such as dump1090 append
+    ///
+    /// Positions with a false flag are low-confidence and can be discarded.
+    pub position_valid: Option<bool>,
+    /// The line that this message was parsed from, if `ParseOptions::keep_raw` was set
+    pub raw: Option<String>,
+    /// The status of the aircraft, decoded from the callsign column of a StatusAircraft message
+    pub status: Option<AircraftStatus>,
 }
 
 impl Message {
     /// Creates a new message of the provided type, with all other fields set to None
-    fn new(message_type: MessageType) -> Message {
+    ///
+    /// ```
+    /// use sbs1::{Message, MessageType};
+    ///
+    /// let mut message = Message::new(MessageType::Click);
+    /// message.aircraft_id = Some(42);
+    /// assert_eq!(None, message.callsign);
+    /// ```
+    pub fn new(message_type: MessageType) -> Message {
         Message {
-            message_type: message_type,
+            message_type,
             session_id: None,
             aircraft_id: None,
             ident: None,
+            icao_address: None,
+            ident_raw: None,
             flight_id: None,
+            #[cfg(feature = "chrono")]
             generated: None,
+            #[cfg(feature = "chrono")]
             logged: None,
             callsign: None,
             altitude: None,
@@ -98,142 +609,3082 @@ impl Message {
             emergency: None,
             special_position: None,
             on_ground: None,
+            rssi: None,
+            emitter_category: None,
+            position_valid: None,
+            raw: None,
+            status: None,
         }
     }
-}
 
-/// Errors that can occur during parsing
-#[derive(Debug, Clone, PartialEq)]
-pub enum ParseError {
-    /// The provided message had an invalid format
-    InvalidLineFormat,
-    /// The message type was invalid
-    InvalidMessageType,
-    /// The transmission type (for messages of type Transmission) was invalid
-    InvalidTransmissionType,
-}
+    /// Returns this message with the provided latitude and longitude
+    ///
+    /// This is useful for making test data, such as a track of positions:
+    ///
+    /// ```
+    /// use sbs1::{MessageBuilder, MessageType, TransmissionType};
+    ///
+    /// let template = MessageBuilder::new(MessageType::Transmission(TransmissionType::EsAirbornePos))
+    ///     .icao_address(0xA1B2C3)
+    ///     .altitude(35000.0)
+    ///     .build();
+    /// let track: Vec<_> = (0..3)
+    ///     .map(|step| template.clone().with_position(47.0 + step as f64 * 0.1, -122.0))
+    ///     .collect();
+    /// assert_eq!(Some((47.1, -122.0)), track[1].position());
+    /// ```
+    pub fn with_position(mut self, latitude: f64, longitude: f64) -> Message {
+        self.latitude = Some(latitude);
+        self.longitude = Some(longitude);
+        self
+    }
 
-impl std::fmt::Display for ParseError {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        write!(f, "Parse error: {}", ::std::error::Error::description(self))
+    /// Returns the ICAO address of the aircraft as six uppercase hexadecimal digits
+    pub fn icao_hex(&self) -> Option<String> {
+        self.icao_address.map(|address| format!("{:06X}", address))
     }
-}
 
-impl std::error::Error for ParseError {
-    fn description(&self) -> &str {
-        match *self {
-            ParseError::InvalidLineFormat => "Invalid line format",
-            ParseError::InvalidMessageType => "Invalid message type",
-            ParseError::InvalidTransmissionType => "Invalid transmission type",
-        }
+    /// Returns the indices of the fields in a line that are not empty, but could not be parsed
+    ///
+    /// This can be used to monitor the quality of a feed, because `parse` sets these fields to
+    /// None in the same way as empty fields. The HexIdent column is reported if it is not a
+    /// valid ICAO address. If the line cannot be parsed at all, the result is empty.
+    pub fn parse_warnings(message_string: &str) -> Vec<usize> {
+        let message = match parse(message_string) {
+            Ok(message) => message,
+            Err(_) => return Vec::new(),
+        };
+        let (parts, count) = match split_fields(message_string, DEFAULT_DELIMITER, &DEFAULT_EXPECTED_FIELDS) {
+            Ok(fields) => fields,
+            Err(_) => return Vec::new(),
+        };
+        let missing = [
+            (2, message.session_id.is_none()),
+            (3, message.aircraft_id.is_none()),
+            (4, message.icao_address.is_none()),
+            (5, message.flight_id.is_none()),
+            #[cfg(feature = "chrono")]
+            (6, message.generated.is_none()),
+            #[cfg(feature = "chrono")]
+            (7, message.generated.is_none()),
+            #[cfg(feature = "chrono")]
+            (8, message.logged.is_none()),
+            #[cfg(feature = "chrono")]
+            (9, message.logged.is_none()),
+            (11, message.altitude.is_none()),
+            (12, message.ground_speed.is_none()),
+            (13, message.track.is_none()),
+            (14, message.latitude.is_none()),
+            (15, message.longitude.is_none()),
+            (16, message.vertical_speed.is_none()),
+            (17, message.squawk.is_none()),
+            (18, message.alert.is_none()),
+            (19, message.emergency.is_none()),
+            (20, message.special_position.is_none()),
+            (21, message.on_ground.is_none()),
+            (22, message.rssi.is_none()),
+            (23, message.emitter_category.is_none()),
+            (24, message.position_valid.is_none()),
+        ];
+        missing.iter()
+            .filter(|&&(index, missing)| {
+                missing && index < count && !parts[index].trim().is_empty()
+            })
+            .map(|&(index, _)| index)
+            .collect()
     }
-}
 
-/// Parses a line of text into a message
-pub fn parse(message_string: &str) -> Result<Message, ParseError> {
-    let parts = message_string.trim().split(',').collect::<Vec<_>>();
-    if parts.len() != 22 {
-        return Err(ParseError::InvalidLineFormat);
+    /// Returns the altitude in meters
+    pub fn altitude_meters(&self) -> Option<f64> {
+        self.altitude.map(|altitude| altitude * METERS_PER_FOOT)
     }
-    let message_type = match parts[0] {
-        "SEL" => MessageType::SelectionChange,
-        "ID" => MessageType::NewId,
-        "AIR" => MessageType::NewAircraft,
-        "STA" => MessageType::StatusAircraft,
-        "CLK" => MessageType::Click,
-        "MSG" => {
-            // Transmission message
-            let transmission_type = match parts[1] {
-                "1" => TransmissionType::EsIdentAndCategory,
-                "2" => TransmissionType::EsSurfacePos,
-                "3" => TransmissionType::EsAirbornePos,
-                "4" => TransmissionType::EsAirborneVel,
-                "5" => TransmissionType::SurveillanceAlt,
-                "6" => TransmissionType::SurveillanceId,
-                "7" => TransmissionType::AirToAir,
-                "8" => TransmissionType::AllCallReply,
-                _ => return Err(ParseError::InvalidTransmissionType),
-            };
-            MessageType::Transmission(transmission_type)
+
+    /// Returns the approximate altitude above sea level, in feet, given the local QNH
+    /// (sea-level pressure) in hectopascals
+    ///
+    /// The reported altitude is a pressure altitude, based on a standard pressure of 1013.25
+    /// hPa. This adds 27 feet for every hectopascal that the QNH is above the standard, and
+    /// subtracts 27 feet for every hectopascal below it. That is only an approximation, which
+    /// ignores temperature and becomes less accurate at higher altitudes.
+    pub fn altitude_corrected(&self, qnh_hpa: f64) -> Option<f64> {
+        self.altitude.map(|altitude| altitude + (qnh_hpa - STANDARD_PRESSURE_HPA) * FEET_PER_HPA)
+    }
+
+    /// Returns the altitude as a flight level (hundreds of feet), rounded to the nearest level
+    ///
+    /// Altitudes below zero have flight level 0.
+    #[cfg(feature = "std")]
+    pub fn altitude_flight_level(&self) -> Option<u32> {
+        self.altitude.map(|altitude| (altitude / 100.0).round() as u32)
+    }
+
+    /// Returns the ground speed in kilometers per hour
+    pub fn ground_speed_kmh(&self) -> Option<f64> {
+        self.ground_speed.map(|speed| speed * KMH_PER_KNOT)
+    }
+
+    /// Returns the ground speed in meters per second
+    pub fn ground_speed_ms(&self) -> Option<f64> {
+        self.ground_speed.map(|speed| speed * MS_PER_KNOT)
+    }
+
+    /// Returns the vertical speed in meters per second
+    pub fn vertical_speed_ms(&self) -> Option<f64> {
+        self.vertical_speed.map(|speed| speed * MS_PER_FOOT_PER_MINUTE)
+    }
+
+    /// Returns the track wrapped into the range from 0 up to, but not including, 360 degrees
+    ///
+    /// Some senders round the track to 360, or report slightly negative values, so 360 becomes
+    /// 0 and -5 becomes 355.
+    pub fn track_normalized(&self) -> Option<f64> {
+        self.track.map(|track| {
+            let wrapped = track % 360.0;
+            // Adding 360 to a tiny negative value can round up to 360
+            if wrapped >= 0.0 { wrapped } else { (wrapped + 360.0) % 360.0 }
+        })
+    }
+
+    /// Returns the track as a point of a 16-point compass, such as N, NNE, or NE
+    ///
+    /// Each point covers the 22.5 degrees centered on its direction, so 11.25 degrees and more
+    /// is NNE.
+    pub fn track_compass(&self) -> Option<&'static str> {
+        const POINTS: [&str; 16] = [
+            "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+            "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+        ];
+        // The normalized track is never negative, so the cast rounds to the nearest point
+        self.track_normalized().map(|track| POINTS[(track / 22.5 + 0.5) as usize % POINTS.len()])
+    }
+
+    /// Returns whether the aircraft is climbing, descending, or flying level, based on the
+    /// vertical speed
+    pub fn climb_state(&self) -> Option<ClimbState> {
+        self.vertical_speed.map(|speed| if speed > LEVEL_VERTICAL_SPEED {
+            ClimbState::Climbing
+        } else if speed < -LEVEL_VERTICAL_SPEED {
+            ClimbState::Descending
+        } else {
+            ClimbState::Level
+        })
+    }
+
+    /// Checks that the position, track, altitude, and timestamps of this message are plausible
+    ///
+    /// Fields that are None are not checked. If more than one field is invalid, the error
+    /// refers to the first invalid field in the order latitude, longitude, track, altitude,
+    /// timestamps. The timestamps are only checked with the chrono feature, and are invalid if
+    /// the message was generated after it was logged.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_with_options(&ValidationOptions::default())
+    }
+
+    /// Checks that the position, track, altitude, and timestamps of this message are
+    /// plausible, with options that enable additional checks
+    pub fn validate_with_options(&self, options: &ValidationOptions) -> Result<(), ValidationError> {
+        if !in_range(self.latitude, -90.0, 90.0) {
+            return Err(ValidationError::LatitudeOutOfRange);
         }
-        _ => return Err(ParseError::InvalidMessageType),
-    };
-    // Create a message
-    let mut message = Message::new(message_type);
-    // Fill in fields
-    message.session_id = parts[2].parse().ok();
-    message.aircraft_id = parts[3].parse().ok();
-    message.ident = parts[4].parse().ok();
-    message.flight_id = parts[5].parse().ok();
-    message.generated = parse_date_time(parts[6], parts[7]).ok();
-    message.logged = parse_date_time(parts[8], parts[9]).ok();
-    message.callsign = if parts[10].is_empty() { None } else { Some(String::from(parts[10].trim())) };
-    message.altitude = parts[11].parse().ok();
-    message.ground_speed = parts[12].parse().ok();
-    message.track = parts[13].parse().ok();
-    message.latitude = parts[14].parse().ok();
-    message.longitude = parts[15].parse().ok();
-    message.vertical_speed = parts[16].parse().ok();
-    message.squawk = parts[17].parse().ok();
-    message.alert = parts[18].parse().ok();
-    message.emergency = parts[19].parse().ok();
-    message.special_position = parts[20].parse().ok();
-    message.on_ground = parts[21].parse().ok();
+        if !in_range(self.longitude, -180.0, 180.0) {
+            return Err(ValidationError::LongitudeOutOfRange);
+        }
+        if options.reject_null_island && self.is_null_island() {
+            return Err(ValidationError::NullIsland);
+        }
+        if let Some(track) = self.track {
+            if !(0.0..360.0).contains(&track) {
+                return Err(ValidationError::TrackOutOfRange);
+            }
+        }
+        if !in_range(self.altitude, MIN_ALTITUDE, MAX_ALTITUDE) {
+            return Err(ValidationError::AltitudeOutOfRange);
+        }
+        #[cfg(feature = "chrono")]
+        {
+            if let (Some(generated), Some(logged)) = (self.generated, self.logged) {
+                if generated > logged {
+                    return Err(ValidationError::TimeOrder);
+                }
+            }
+        }
+        Ok(())
+    }
 
-    Ok(message)
-}
+    /// Returns the latitude and longitude of the aircraft, or None if either is missing
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => Some((latitude, longitude)),
+            _ => None,
+        }
+    }
 
-/// Parses a date component and a time component into a DateTime
-fn parse_date_time(date: &str, time: &str) -> Result<DateTime<Local>, chrono::format::ParseError> {
-    let combined = format!("{} {}", date.trim(), time.trim());
-    Local.datetime_from_str(&combined, DATE_TIME_FORMAT)
-}
+    /// Returns the latitude and longitude, or an error naming the first one that is missing
+    pub fn require_position(&self) -> Result<(f64, f64), MissingField> {
+        Ok((require(self.latitude, "latitude")?, require(self.longitude, "longitude")?))
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn test_empty_string() {
-        let result = parse("");
-        assert_eq!(Err(ParseError::InvalidLineFormat), result);
+    /// Returns the ICAO address, or an error if it is missing
+    pub fn require_icao(&self) -> Result<u32, MissingField> {
+        require(self.icao_address, "icao_address")
     }
-    #[test]
-    fn test_few_commas() {
-        let result = parse(",,,,");
-        assert_eq!(Err(ParseError::InvalidLineFormat), result);
+
+    /// Returns the altitude, or an error if it is missing
+    pub fn require_altitude(&self) -> Result<f64, MissingField> {
+        require(self.altitude, "altitude")
     }
-    #[test]
-    fn test_correct_commas_empty() {
-        let result = parse(",,,,,,,,,,,,,,,,,,,,,");
-        assert_eq!(Err(ParseError::InvalidMessageType), result);
+
+    /// Returns the callsign, or an error if it is missing
+    pub fn require_callsign(&self) -> Result<&str, MissingField> {
+        require(self.callsign.as_deref(), "callsign")
     }
-    #[test]
-    fn test_selection_change() {
-        let result = parse("SEL,,,,,,,,,,,,,,,,,,,,,");
-        let expected = Ok(Message::new(MessageType::SelectionChange));
-        assert_eq!(expected, result);
+
+    /// Returns true if the latitude and longitude are both exactly 0
+    ///
+    /// Receivers often report this position when they do not have a fix, so it is rarely a
+    /// real aircraft position.
+    pub fn is_null_island(&self) -> bool {
+        self.position() == Some((0.0, 0.0))
     }
-    #[test]
-    fn test_selection_change_vertical_speed() {
-        let result = parse("SEL,,,,,,,,,,,,,,,,-350,,,,,");
-        assert!(!result.is_err());
-        let result = result.unwrap();
-        assert_eq!(MessageType::SelectionChange, result.message_type);
-        assert!(result.vertical_speed.is_some());
-        assert_eq!(-350_f64, result.vertical_speed.unwrap());
+
+    /// Returns true if the message has both a latitude and a longitude
+    pub fn has_position(&self) -> bool {
+        self.position().is_some()
     }
 
-    #[test]
-    fn test_date_time_parse() {
-        let date = "2016/03/11";
-        let time = "21:24:53.351";
-        let result = super::parse_date_time(date, time);
-        match result {
-            Ok(_) => {},
-            Err(e) => {
-                println!("{:?}", e);
-                assert!(false);
+    /// Returns the aircraft ID of the aircraft that the user clicked, if this is a Click message
+    ///
+    /// Returns None for other message types, even if they have an aircraft ID.
+    pub fn clicked_aircraft(&self) -> Option<u32> {
+        match self.message_type {
+            MessageType::Click => self.aircraft_id,
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is an airborne or surface position transmission with both a
+    /// latitude and a longitude
+    pub fn is_position_report(&self) -> bool {
+        self.has_position() && matches!(self.message_type.transmission_type(),
+                                        Some(&TransmissionType::EsSurfacePos) |
+                                        Some(&TransmissionType::EsAirbornePos))
+    }
+
+    /// Returns the transmission type code as it appears in the second field of a line, such as
+    /// "3", or None if this message is not a transmission
+    pub fn transmission_code_str(&self) -> Option<&'static str> {
+        self.message_type.transmission_type().map(|transmission_type| {
+            ["1", "2", "3", "4", "5", "6", "7", "8"][usize::from(transmission_type.code()) - 1]
+        })
+    }
+
+    /// Returns true if this message and another message report the same position of the same
+    /// aircraft
+    ///
+    /// The ICAO addresses, latitudes, longitudes, and altitudes are compared, and timestamps
+    /// are ignored. Messages that do not have both a latitude and a longitude never report the
+    /// same position.
+    pub fn same_position_report(&self, other: &Message) -> bool {
+        self.has_position() &&
+            self.icao_address == other.icao_address &&
+            self.latitude == other.latitude &&
+            self.longitude == other.longitude &&
+            self.altitude == other.altitude
+    }
+
+    /// Returns whether this message and another message refer to the same aircraft
+    ///
+    /// The ICAO addresses are compared if both messages have one. Otherwise, the aircraft IDs
+    /// are compared if both messages have one. If neither identifier is present in both
+    /// messages, returns None.
+    pub fn same_aircraft(&self, other: &Message) -> Option<bool> {
+        match ((self.icao_address, other.icao_address), (self.aircraft_id, other.aircraft_id)) {
+            ((Some(mine), Some(theirs)), _) | (_, (Some(mine), Some(theirs))) => Some(mine == theirs),
+            _ => None,
+        }
+    }
+
+    /// Returns a GeoJSON Point feature at the position of this message, or None if the
+    /// message does not have a latitude and longitude
+    ///
+    /// The properties of the feature are the altitude, callsign, track, and ICAO address
+    /// (in hexadecimal). Properties that are not known are null.
+    #[cfg(feature = "serde")]
+    pub fn to_geojson_feature(&self) -> Option<serde_json::Value> {
+        self.position().map(|(latitude, longitude)| json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [longitude, latitude],
+            },
+            "properties": {
+                "altitude": self.altitude,
+                "callsign": self.callsign,
+                "track": self.track,
+                "icao_hex": self.icao_hex(),
             },
+        }))
+    }
+
+    /// Returns the great-circle distance in kilometers between the positions of this message
+    /// and another message, or None if either message does not have a latitude and longitude
+    ///
+    /// This uses the haversine formula with a spherical Earth, so it may be off by up to
+    /// about 0.5%.
+    #[cfg(feature = "std")]
+    pub fn distance_to(&self, other: &Message) -> Option<f64> {
+        let (latitude1, longitude1) = radians(self.position()?);
+        let (latitude2, longitude2) = radians(other.position()?);
+        let half_chord = ((latitude2 - latitude1) / 2.0).sin().powi(2) +
+            latitude1.cos() * latitude2.cos() * ((longitude2 - longitude1) / 2.0).sin().powi(2);
+        Some(2.0 * EARTH_RADIUS_KM * half_chord.sqrt().asin())
+    }
+
+    /// Returns the initial bearing in degrees (0 to 360, clockwise from true north) along the
+    /// great circle from the position of this message to the position of another message, or
+    /// None if either message does not have a latitude and longitude
+    #[cfg(feature = "std")]
+    pub fn bearing_to(&self, other: &Message) -> Option<f64> {
+        let (latitude1, longitude1) = radians(self.position()?);
+        let (latitude2, longitude2) = radians(other.position()?);
+        let delta_longitude = longitude2 - longitude1;
+        let y = delta_longitude.sin() * latitude2.cos();
+        let x = latitude1.cos() * latitude2.sin() -
+            latitude1.sin() * latitude2.cos() * delta_longitude.cos();
+        Some((y.atan2(x).to_degrees() + 360.0) % 360.0)
+    }
+
+    /// Combines the fields of another message from the same aircraft into this message
+    ///
+    /// Fields of this message that are None are filled in from the other message. If the other
+    /// message was generated after this message, all fields that the other message has
+    /// replace the fields of this message. The message type and raw line are not changed.
+    ///
+    /// If the messages do not have the same ICAO address, this message is not changed.
+    pub fn merge(&mut self, other: &Message) {
+        if self.icao_address.is_none() || self.icao_address != other.icao_address {
+            return;
+        }
+        #[cfg(feature = "chrono")]
+        let newer = match (self.generated, other.generated) {
+            (Some(generated), Some(other_generated)) => other_generated > generated,
+            _ => false,
+        };
+        #[cfg(not(feature = "chrono"))]
+        let newer = false;
+
+        merge_field(&mut self.session_id, &other.session_id, newer);
+        merge_field(&mut self.aircraft_id, &other.aircraft_id, newer);
+        merge_field(&mut self.ident, &other.ident, newer);
+        merge_field(&mut self.ident_raw, &other.ident_raw, newer);
+        merge_field(&mut self.flight_id, &other.flight_id, newer);
+        #[cfg(feature = "chrono")]
+        {
+            merge_field(&mut self.generated, &other.generated, newer);
+            merge_field(&mut self.logged, &other.logged, newer);
+        }
+        merge_field(&mut self.callsign, &other.callsign, newer);
+        merge_field(&mut self.altitude, &other.altitude, newer);
+        merge_field(&mut self.ground_speed, &other.ground_speed, newer);
+        merge_field(&mut self.track, &other.track, newer);
+        merge_field(&mut self.latitude, &other.latitude, newer);
+        merge_field(&mut self.longitude, &other.longitude, newer);
+        merge_field(&mut self.vertical_speed, &other.vertical_speed, newer);
+        merge_field(&mut self.squawk, &other.squawk, newer);
+        merge_field(&mut self.alert, &other.alert, newer);
+        merge_field(&mut self.emergency, &other.emergency, newer);
+        merge_field(&mut self.special_position, &other.special_position, newer);
+        merge_field(&mut self.on_ground, &other.on_ground, newer);
+        merge_field(&mut self.rssi, &other.rssi, newer);
+        merge_field(&mut self.emitter_category, &other.emitter_category, newer);
+        merge_field(&mut self.position_valid, &other.position_valid, newer);
+        merge_field(&mut self.status, &other.status, newer);
+    }
+
+    /// Returns the number of optional fields that have a value
+    ///
+    /// This is a quick measure of how complete a message is, for choosing between reports. The
+    /// raw line is not counted.
+    pub fn field_count(&self) -> usize {
+        let present = [
+            self.session_id.is_some(),
+            self.aircraft_id.is_some(),
+            self.ident.is_some(),
+            self.icao_address.is_some(),
+            self.ident_raw.is_some(),
+            self.flight_id.is_some(),
+            #[cfg(feature = "chrono")]
+            self.generated.is_some(),
+            #[cfg(feature = "chrono")]
+            self.logged.is_some(),
+            self.callsign.is_some(),
+            self.altitude.is_some(),
+            self.ground_speed.is_some(),
+            self.track.is_some(),
+            self.latitude.is_some(),
+            self.longitude.is_some(),
+            self.vertical_speed.is_some(),
+            self.squawk.is_some(),
+            self.alert.is_some(),
+            self.emergency.is_some(),
+            self.special_position.is_some(),
+            self.on_ground.is_some(),
+            self.rssi.is_some(),
+            self.emitter_category.is_some(),
+            self.position_valid.is_some(),
+            self.status.is_some(),
+        ];
+        present.iter().filter(|&&present| present).count()
+    }
+
+    /// Returns the callsign in uppercase, without surrounding whitespace, for comparing
+    /// callsigns without regard to case
+    ///
+    /// Returns None if the callsign is missing or only whitespace.
+    pub fn callsign_trimmed_upper(&self) -> Option<String> {
+        self.callsign.as_ref()
+            .map(|callsign| callsign.trim().to_uppercase())
+            .filter(|callsign| !callsign.is_empty())
+    }
+
+    /// Returns the name of the country that the ICAO address of the aircraft is allocated to
+    ///
+    /// Returns None if the message does not have an ICAO address, or the address is not
+    /// allocated to a country.
+    pub fn country(&self) -> Option<&'static str> {
+        self.icao_address.and_then(icao::country)
+    }
+
+    /// Returns true if the ICAO address of the aircraft is in a block that is known to be used
+    /// by military aircraft
+    ///
+    /// Only a few well-known blocks are checked. Returns false if the message does not have an
+    /// ICAO address, or the address is not in a known military block.
+    pub fn is_military(&self) -> bool {
+        self.icao_address.is_some_and(icao::is_military)
+    }
+
+    /// Returns the transponder code as four digits, such as "0007" or "1200"
+    ///
+    /// The digits are the same as the stored value, padded with leading zeros.
+    pub fn squawk_string(&self) -> Option<String> {
+        self.squawk.map(|squawk| format!("{:04}", squawk))
+    }
+
+    /// Returns whether the aircraft is on the ground
+    ///
+    /// The on-ground flag is used if it is present. Otherwise, a surface position transmission
+    /// means that the aircraft is on the ground. If neither is available, returns None.
+    pub fn is_on_ground(&self) -> Option<bool> {
+        match (self.on_ground, self.message_type.transmission_type()) {
+            (Some(on_ground), _) => Some(on_ground),
+            (None, Some(&TransmissionType::EsSurfacePos)) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the alert flag is set, which means that the transponder code has changed
+    ///
+    /// A missing flag counts as not set.
+    pub fn transponder_changed(&self) -> bool {
+        self.alert.unwrap_or(false)
+    }
+
+    /// Returns true if the Special Position Indicator flag is set
+    pub fn special_position_active(&self) -> bool {
+        self.special_position.unwrap_or(false)
+    }
+
+    /// Returns true if the emergency flag is set
+    pub fn emergency_active(&self) -> bool {
+        self.emergency.unwrap_or(false)
+    }
+
+    /// Returns whether the aircraft is on the ground or in the air
+    ///
+    /// Surface and airborne position transmissions are always of that kind. For other
+    /// messages, the on-ground flag is used. If there is no flag, returns None.
+    pub fn position_kind(&self) -> Option<PositionKind> {
+        match self.message_type.transmission_type() {
+            Some(&TransmissionType::EsSurfacePos) => Some(PositionKind::Surface),
+            Some(&TransmissionType::EsAirbornePos) => Some(PositionKind::Airborne),
+            _ => self.on_ground.map(|on_ground| {
+                if on_ground { PositionKind::Surface } else { PositionKind::Airborne }
+            }),
         }
     }
+
+    /// Returns the type of emergency indicated by the squawk code, if any
+    ///
+    /// This only depends on the squawk code, because many feeds do not set the emergency flag.
+    pub fn emergency_kind(&self) -> Option<EmergencyKind> {
+        match self.squawk {
+            Some(7500) => Some(EmergencyKind::Hijack),
+            Some(7600) => Some(EmergencyKind::RadioFailure),
+            Some(7700) => Some(EmergencyKind::GeneralEmergency),
+            _ => None,
+        }
+    }
+
+    /// Returns a hash of the contents of this message, for use as a storage key
+    ///
+    /// The hash covers the fields returned by `fields`, except the logged time, so messages
+    /// that were logged at slightly different times have the same hash. The generated time is
+    /// hashed as an instant, independent of the time zone. The hash is 64-bit FNV-1a, which
+    /// does not change between platforms or versions of Rust.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        for (name, value) in self.fields() {
+            if name == "logged" || name == "generated" {
+                continue;
+            }
+            hash = fnv1a(hash, name.as_bytes());
+            hash = match value {
+                Some(value) => fnv1a(fnv1a(hash, &[1]), value.as_bytes()),
+                None => fnv1a(hash, &[0]),
+            };
+            // Separates this field from the next one
+            hash = fnv1a(hash, &[0xFF]);
+        }
+        #[cfg(feature = "chrono")]
+        {
+            if let Some(generated) = self.generated {
+                hash = fnv1a(hash, &generated.timestamp().to_le_bytes());
+                hash = fnv1a(hash, &generated.timestamp_subsec_nanos().to_le_bytes());
+            }
+        }
+        hash
+    }
+
+    /// Returns the name and value of every field of this message, as text
+    ///
+    /// The fields are in the same order as the fields of this struct, with the same names.
+    /// Values are formatted in the same way as `to_csv_row`, and fields that are None have no
+    /// value. The raw line is not included.
+    pub fn fields(&self) -> Vec<(&'static str, Option<String>)> {
+        fn text<T: ToString>(value: &Option<T>) -> Option<String> {
+            value.as_ref().map(|value| value.to_string())
+        }
+        vec![
+            ("message_type", Some(self.message_type.to_string())),
+            ("session_id", text(&self.session_id)),
+            ("aircraft_id", text(&self.aircraft_id)),
+            ("ident", text(&self.ident)),
+            ("icao_address", self.icao_hex()),
+            ("ident_raw", self.ident_raw.clone()),
+            ("flight_id", text(&self.flight_id)),
+            #[cfg(feature = "chrono")]
+            ("generated", self.generated.map(|generated| generated.to_rfc3339())),
+            #[cfg(feature = "chrono")]
+            ("logged", self.logged.map(|logged| logged.to_rfc3339())),
+            ("callsign", self.callsign.clone()),
+            ("altitude", text(&self.altitude)),
+            ("ground_speed", text(&self.ground_speed)),
+            ("track", text(&self.track)),
+            ("latitude", text(&self.latitude)),
+            ("longitude", text(&self.longitude)),
+            ("vertical_speed", text(&self.vertical_speed)),
+            ("squawk", text(&self.squawk)),
+            ("alert", text(&self.alert)),
+            ("emergency", text(&self.emergency)),
+            ("special_position", text(&self.special_position)),
+            ("on_ground", text(&self.on_ground)),
+            ("rssi", text(&self.rssi)),
+            ("emitter_category", self.emitter_category.map(|category| format!("{:02X}", category))),
+            ("position_valid", text(&self.position_valid)),
+            ("status", self.status.as_ref().map(|status| status.code().to_owned())),
+        ]
+    }
+
+    /// Returns a short summary of this message on one line, for reading a feed on a console
+    ///
+    /// The summary starts with the message type, such as MSG3 or AIR, followed by the fields
+    /// that are present: the ICAO address, callsign, altitude, ground speed, track, and
+    /// position. For example: `MSG3 A1B2C3 DLH123 35000ft 450kt hdg123 @ 50.1,8.5`.
+    pub fn summary_line(&self) -> String {
+        let mut line = format!("{}{}", self.message_type.type_token(),
+                               self.transmission_code_str().unwrap_or(""));
+        let mut add = |token: Option<String>| if let Some(token) = token {
+            line.push(' ');
+            line.push_str(&token);
+        };
+        add(self.icao_hex());
+        add(self.callsign.clone());
+        add(self.altitude.map(|altitude| format!("{}ft", altitude)));
+        add(self.ground_speed.map(|speed| format!("{}kt", speed)));
+        add(self.track.map(|track| format!("hdg{}", track)));
+        add(self.position().map(|(latitude, longitude)| format!("@ {},{}", latitude, longitude)));
+        line
+    }
+
+    /// Returns the header line for CSV files written with `to_csv_row`
+    pub fn csv_header() -> &'static str {
+        "message_type,session_id,aircraft_id,ident,icao_address,ident_raw,flight_id,generated,\
+         logged,callsign,altitude,ground_speed,track,latitude,longitude,vertical_speed,squawk,\
+         alert,emergency,special_position,on_ground,rssi,emitter_category,position_valid,status"
+    }
+
+    /// Formats this message as a CSV row, with the columns listed in `csv_header`
+    ///
+    /// The columns are in the same order as the fields of this struct, and are the same as the
+    /// fields that `fields` returns. Fields that are None are empty. The ICAO address and the
+    /// emitter category are written in hexadecimal, and timestamps are written in RFC 3339
+    /// format. Without the chrono feature, the timestamp columns are always empty.
+    pub fn to_csv_row(&self) -> String {
+        #[cfg(feature = "chrono")]
+        let (generated, logged) = (
+            self.generated.map(|generated| generated.to_rfc3339()).unwrap_or_default(),
+            self.logged.map(|logged| logged.to_rfc3339()).unwrap_or_default(),
+        );
+        #[cfg(not(feature = "chrono"))]
+        let (generated, logged) = (String::new(), String::new());
+        let fields = [
+            self.message_type.to_string(),
+            format_option(&self.session_id),
+            format_option(&self.aircraft_id),
+            format_option(&self.ident),
+            self.icao_hex().unwrap_or_default(),
+            escape_csv(&format_option(&self.ident_raw)),
+            format_option(&self.flight_id),
+            generated,
+            logged,
+            escape_csv(&format_option(&self.callsign)),
+            format_option(&self.altitude),
+            format_option(&self.ground_speed),
+            format_option(&self.track),
+            format_option(&self.latitude),
+            format_option(&self.longitude),
+            format_option(&self.vertical_speed),
+            format_option(&self.squawk),
+            format_option(&self.alert),
+            format_option(&self.emergency),
+            format_option(&self.special_position),
+            format_option(&self.on_ground),
+            format_option(&self.rssi),
+            self.emitter_category.map(|category| format!("{:02X}", category)).unwrap_or_default(),
+            format_option(&self.position_valid),
+            self.status.as_ref().map(|status| status.code().to_owned()).unwrap_or_default(),
+        ];
+        fields.join(",")
+    }
+
+    /// Formats this message as a line of SBS-1 text, without a line terminator
+    ///
+    /// The returned line has the same 22 fields that `parse` expects, and parsing it produces
+    /// a message with the same values, to the millisecond: timestamps are written with
+    /// milliseconds, and any finer precision is lost. Fields that are None are left empty. If
+    /// the message has a signal strength, it is written as a 23rd field. An emitter category is
+    /// written as a 24th field, and the position validity flag as a 25th field, as -1 or 0. The
+    /// extra fields are written up to the last one that has a value, with the ones before it
+    /// left empty if they are None.
+    ///
+    /// The HexIdent column is written from `icao_address` if it is present, then from
+    /// `ident_raw`, and from `ident` otherwise. Parsing fills in all three from that column,
+    /// so the parsed message is only equal to this one if they agree with each other. For
+    /// example, a message with only `icao_address` set to 0x123456 is parsed with `ident_raw`
+    /// set to "123456" and `ident` set to 123456 as well. Writing the parsed message gives the
+    /// same line again. A callsign that contains a comma, a quote, or a
+    /// line break is written in quotes as in CSV, with any quotes in it doubled.
+    pub fn to_sbs1_string(&self) -> String {
+        #[cfg(feature = "chrono")]
+        let ((generated_date, generated_time), (logged_date, logged_time)) =
+            (format_date_time(&self.generated), format_date_time(&self.logged));
+        #[cfg(not(feature = "chrono"))]
+        let ((generated_date, generated_time), (logged_date, logged_time)) =
+            ((String::new(), String::new()), (String::new(), String::new()));
+        let mut fields = vec![
+            self.message_type.type_token().to_owned(),
+            self.transmission_code_str().unwrap_or("").to_owned(),
+            format_option(&self.session_id),
+            format_option(&self.aircraft_id),
+            self.icao_hex()
+                .or_else(|| self.ident_raw.clone())
+                .unwrap_or_else(|| format_option(&self.ident)),
+            format_option(&self.flight_id),
+            generated_date,
+            generated_time,
+            logged_date,
+            logged_time,
+            escape_csv(&format_option(&self.callsign)),
+            format_option(&self.altitude),
+            format_option(&self.ground_speed),
+            format_option(&self.track),
+            format_option(&self.latitude),
+            format_option(&self.longitude),
+            format_option(&self.vertical_speed),
+            format_option(&self.squawk),
+            format_flag(&self.alert),
+            format_flag(&self.emergency),
+            format_flag(&self.special_position),
+            format_flag(&self.on_ground),
+        ];
+        // The extra columns are written up to the last one that has a value
+        if self.rssi.is_some() || self.emitter_category.is_some() || self.position_valid.is_some() {
+            fields.push(format_option(&self.rssi));
+        }
+        if self.emitter_category.is_some() || self.position_valid.is_some() {
+            fields.push(self.emitter_category.map(|category| format!("{:02X}", category))
+                .unwrap_or_default());
+        }
+        if self.position_valid.is_some() {
+            fields.push(format_flag(&self.position_valid));
+        }
+        fields.join(",")
+    }
+}
+
+/// Copies a field from another message if the other message has it, and the field is None
+/// or replace is true
+fn merge_field<T: Clone>(field: &mut Option<T>, other: &Option<T>, replace: bool) {
+    if other.is_some() && (replace || field.is_none()) {
+        *field = other.clone();
+    }
+}
+
+/// Converts a latitude and longitude in degrees to radians
+#[cfg(feature = "std")]
+fn radians((latitude, longitude): (f64, f64)) -> (f64, f64) {
+    (latitude.to_radians(), longitude.to_radians())
+}
+
+/// The initial value of a 64-bit FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+/// The multiplier of a 64-bit FNV-1a hash
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Adds some bytes to a 64-bit FNV-1a hash
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Returns a value, or a MissingField error naming the field if it is None
+fn require<T>(value: Option<T>, field: FieldName) -> Result<T, MissingField> {
+    value.ok_or(MissingField { field })
+}
+
+/// Returns true if a value is None, or is between min and max inclusive
+fn in_range(value: Option<f64>, min: f64, max: f64) -> bool {
+    value.is_none_or(|value| (min..=max).contains(&value))
+}
+
+/// An SBS-1 message that borrows its text fields from the line it was parsed from
+///
+/// The fields have the same meanings as the fields of `Message`. The callsign is only copied
+/// if it is quoted and has doubled quotes in it, which have to be undoubled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageRef<'a> {
+    pub message_type: MessageType,
+    pub session_id: Option<u32>,
+    pub aircraft_id: Option<u32>,
+    pub ident: Option<u32>,
+    pub icao_address: Option<u32>,
+    pub ident_raw: Option<&'a str>,
+    pub flight_id: Option<u32>,
+    #[cfg(feature = "chrono")]
+    pub generated: Option<DateTime<Local>>,
+    #[cfg(feature = "chrono")]
+    pub logged: Option<DateTime<Local>>,
+    pub callsign: Option<Cow<'a, str>>,
+    pub altitude: Option<f64>,
+    pub ground_speed: Option<f64>,
+    pub track: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub vertical_speed: Option<f64>,
+    pub squawk: Option<u16>,
+    pub alert: Option<bool>,
+    pub emergency: Option<bool>,
+    pub special_position: Option<bool>,
+    pub on_ground: Option<bool>,
+    pub rssi: Option<f64>,
+    pub emitter_category: Option<u8>,
+    pub position_valid: Option<bool>,
+    pub raw: Option<&'a str>,
+    pub status: Option<AircraftStatus>,
+}
+
+impl<'a> MessageRef<'a> {
+    /// Creates a new message of the provided type, with all other fields set to None
+    fn new(message_type: MessageType) -> MessageRef<'a> {
+        MessageRef {
+            message_type,
+            session_id: None,
+            aircraft_id: None,
+            ident: None,
+            icao_address: None,
+            ident_raw: None,
+            flight_id: None,
+            #[cfg(feature = "chrono")]
+            generated: None,
+            #[cfg(feature = "chrono")]
+            logged: None,
+            callsign: None,
+            altitude: None,
+            ground_speed: None,
+            track: None,
+            latitude: None,
+            longitude: None,
+            vertical_speed: None,
+            squawk: None,
+            alert: None,
+            emergency: None,
+            special_position: None,
+            on_ground: None,
+            rssi: None,
+            emitter_category: None,
+            position_valid: None,
+            raw: None,
+            status: None,
+        }
+    }
+
+    /// Converts this message into a Message that owns all its fields
+    pub fn to_owned(&self) -> Message {
+        let mut message = Message::new(self.message_type.clone());
+        self.copy_into(&mut message);
+        message
+    }
+
+    /// Replaces all the fields of a message with the fields of this message
+    ///
+    /// The text fields reuse the strings that the message already has, if any.
+    fn copy_into(&self, message: &mut Message) {
+        message.message_type = self.message_type.clone();
+        message.session_id = self.session_id;
+        message.aircraft_id = self.aircraft_id;
+        message.ident = self.ident;
+        message.icao_address = self.icao_address;
+        copy_text(self.ident_raw, &mut message.ident_raw);
+        message.flight_id = self.flight_id;
+        #[cfg(feature = "chrono")]
+        {
+            message.generated = self.generated;
+            message.logged = self.logged;
+        }
+        copy_text(self.callsign.as_deref(), &mut message.callsign);
+        message.altitude = self.altitude;
+        message.ground_speed = self.ground_speed;
+        message.track = self.track;
+        message.latitude = self.latitude;
+        message.longitude = self.longitude;
+        message.vertical_speed = self.vertical_speed;
+        message.squawk = self.squawk;
+        message.alert = self.alert;
+        message.emergency = self.emergency;
+        message.special_position = self.special_position;
+        message.on_ground = self.on_ground;
+        message.rssi = self.rssi;
+        message.emitter_category = self.emitter_category;
+        message.position_valid = self.position_valid;
+        copy_text(self.raw, &mut message.raw);
+        message.status = self.status.clone();
+    }
+}
+
+/// Copies borrowed text into an optional string, reusing the string if there is one
+fn copy_text(text: Option<&str>, field: &mut Option<String>) {
+    if let (Some(text), Some(existing)) = (text, field.as_mut()) {
+        existing.clear();
+        existing.push_str(text);
+        return;
+    }
+    *field = text.map(String::from);
+}
+
+impl FromStr for Message {
+    type Err = ParseError;
+
+    /// Parses a line of text into a message, in the same way as `parse`
+    fn from_str(message_string: &str) -> Result<Message, ParseError> {
+        parse(message_string)
+    }
+}
+
+/// Formats an optional value for an SBS-1 field, with None as an empty string
+fn format_option<T: Display>(value: &Option<T>) -> String {
+    match *value {
+        Some(ref value) => value.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Formats an optional flag for an SBS-1 field, using -1 for true and 0 for false as
+/// BaseStation does
+fn format_flag(value: &Option<bool>) -> String {
+    match *value {
+        Some(true) => String::from("-1"),
+        Some(false) => String::from("0"),
+        None => String::new(),
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, a quote, or a line break
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// The name of a field in a `ParseError`
+///
+/// This is an alias so that the serde derive does not try to borrow the name from its input.
+type FieldName = &'static str;
+
+/// Errors that can occur during parsing
+///
+/// A timestamp that cannot be parsed is None in the message rather than an error, so these
+/// errors never have a `source`. To find out why a timestamp could not be parsed, use
+/// `parse_detailed`, whose `TimestampError`s have the chrono error as their source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseError {
+    /// The provided message had an invalid format
+    InvalidLineFormat,
+    /// The message type was not one of the known types
+    InvalidMessageType,
+    /// The message type field was empty
+    ///
+    /// This is only returned when `ParseOptions::empty_message_type` is enabled. Otherwise, an
+    /// empty message type is an `InvalidMessageType`.
+    EmptyMessageType,
+    /// The transmission type (for messages of type Transmission) was invalid
+    InvalidTransmissionType,
+    /// A field was not empty, but its value could not be parsed
+    ///
+    /// This is only returned when strict numeric parsing is enabled in `ParseOptions`.
+    FieldError {
+        /// The zero-based index of the field in the line
+        index: usize,
+        /// The name of the field
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "serde_support::field_name"))]
+        field: FieldName,
+    },
+    /// The squawk code had more than four digits, or a digit that was not octal
+    ///
+    /// This is only returned when squawk validation is enabled in `ParseOptions`.
+    InvalidSquawk,
+    /// A session, aircraft, or flight ID was a whole number that was negative or too large
+    ///
+    /// This is only returned when ID range checking is enabled in `ParseOptions`.
+    NumericOverflow {
+        /// The name of the field
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "serde_support::field_name"))]
+        field: FieldName,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            ParseError::InvalidLineFormat => write!(f, "Parse error: Invalid line format"),
+            ParseError::InvalidMessageType => write!(f, "Parse error: Invalid message type"),
+            ParseError::EmptyMessageType => write!(f, "Parse error: Empty message type"),
+            ParseError::InvalidTransmissionType => {
+                write!(f, "Parse error: Invalid transmission type")
+            }
+            ParseError::FieldError { index, field } => {
+                write!(f, "Parse error: Invalid value in column {} ({})", index, field)
+            }
+            ParseError::InvalidSquawk => write!(f, "Parse error: Invalid squawk code"),
+            ParseError::NumericOverflow { field } => {
+                write!(f, "Parse error: Value out of range ({})", field)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Errors that indicate a message contains implausible values
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ValidationError {
+    /// The latitude was not between -90 and 90 degrees
+    LatitudeOutOfRange,
+    /// The longitude was not between -180 and 180 degrees
+    LongitudeOutOfRange,
+    /// The track was not at least 0 and less than 360 degrees
+    TrackOutOfRange,
+    /// The altitude was not between -1500 and 100000 feet
+    AltitudeOutOfRange,
+    /// The latitude and longitude were both 0, which usually means that there was no fix
+    NullIsland,
+    /// The message was generated after it was logged, which usually means that a clock is wrong
+    TimeOrder,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let problem = match *self {
+            ValidationError::LatitudeOutOfRange => "Latitude out of range",
+            ValidationError::LongitudeOutOfRange => "Longitude out of range",
+            ValidationError::TrackOutOfRange => "Track out of range",
+            ValidationError::AltitudeOutOfRange => "Altitude out of range",
+            ValidationError::NullIsland => "Position is 0, 0",
+            ValidationError::TimeOrder => "Generated after logged",
+        };
+        write!(f, "Validation error: {}", problem)
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
+/// The error returned when a field that is required is None
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MissingField {
+    /// The name of the field
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "serde_support::message_field_name"))]
+    pub field: FieldName,
+}
+
+impl Display for MissingField {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Missing field: {}", self.field)
+    }
+}
+
+impl core::error::Error for MissingField {}
+
+/// Options that control which checks `Message::validate_with_options` makes
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationOptions {
+    /// If this is true, a position with a latitude and longitude of exactly 0 causes a
+    /// `ValidationError::NullIsland`
+    pub reject_null_island: bool,
+}
+
+/// Options that control how lines are parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// If this is true, a numeric field (altitude, ground speed, track, latitude, longitude,
+    /// vertical speed, squawk, or signal strength) that is not empty but cannot be parsed causes a
+    /// `ParseError::FieldError`. Otherwise, the field is set to None.
+    pub strict_numeric: bool,
+    /// If this is true, a squawk code with more than four digits or with any digit greater
+    /// than 7 causes a `ParseError::InvalidSquawk`
+    pub validate_squawk: bool,
+    /// If this is true, the generated and logged dates and times are interpreted as UTC.
+    /// Otherwise, they are interpreted in the local time zone.
+    ///
+    /// BaseStation feeds usually use UTC.
+    #[cfg(feature = "chrono")]
+    pub utc_timestamps: bool,
+    /// If this is true, the line is stored in the `raw` field of the message, exactly as it
+    /// was provided
+    pub keep_raw: bool,
+    /// If this is true, a comma in a numeric field is read as a decimal point, so 35000,5 is
+    /// 35000.5
+    ///
+    /// This is only useful with `parse_with_delimiter_and_options` and a delimiter other than
+    /// a comma.
+    pub decimal_comma: bool,
+    /// If this is true, a session, aircraft, or flight ID that is a negative number or is
+    /// larger than the largest u32 causes a `ParseError::NumericOverflow`. Otherwise, the ID is
+    /// set to None.
+    pub check_id_range: bool,
+    /// If this is true, a line with an empty message type field causes a
+    /// `ParseError::EmptyMessageType`, so it can be told apart from a line with an unknown
+    /// message type. Otherwise, it causes a `ParseError::InvalidMessageType`.
+    pub empty_message_type: bool,
+    /// If this is true, characters other than digits are removed from the end of each numeric
+    /// field before it is parsed, so units such as ft in 35000ft are ignored
+    pub numeric_strip_nonnumeric: bool,
+    /// The numbers of fields that a line can have. Fields that are missing from the end of a
+    /// shorter line are None. A line with a number of fields outside this range causes a
+    /// `ParseError::InvalidLineFormat`.
+    ///
+    /// The default accepts 22 to 25 fields. Lines with more than 25 fields are never accepted.
+    pub expected_fields: Range<usize>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            strict_numeric: false,
+            validate_squawk: false,
+            #[cfg(feature = "chrono")]
+            utc_timestamps: false,
+            keep_raw: false,
+            decimal_comma: false,
+            check_id_range: false,
+            empty_message_type: false,
+            numeric_strip_nonnumeric: false,
+            expected_fields: DEFAULT_EXPECTED_FIELDS,
+        }
+    }
+}
+
+/// Parses a line of text into a message
+pub fn parse(message_string: &str) -> Result<Message, ParseError> {
+    parse_with_options(message_string, &ParseOptions::default())
+}
+
+/// Parses each line of some text into a message
+///
+/// Each result is returned with its line number, starting at 1. Blank lines are skipped. A
+/// quoted field that continues onto the next line is parsed as part of one message, which has
+/// the number of the line that it starts on.
+pub fn parse_lines(input: &str) -> Vec<(usize, Result<Message, ParseError>)> {
+    let mut records = ParseIter::new(input);
+    core::iter::from_fn(|| records.next_record())
+        .map(|(line_number, line)| (line_number, parse(line)))
+        .collect()
+}
+
+/// Parses each line of some text into a message, as the lines are iterated over
+///
+/// This is the lazy version of `parse_lines`, without line numbers. Blank lines are skipped.
+pub fn parse_iter(input: &str) -> ParseIter<'_> {
+    ParseIter::new(input)
+}
+
+/// An iterator over the messages parsed from some text, created by `parse_iter`
+#[derive(Debug, Clone)]
+pub struct ParseIter<'a> {
+    /// The text that has not been parsed yet
+    rest: &'a str,
+    /// The number of lines that have been read so far
+    line_number: usize,
+}
+
+impl<'a> ParseIter<'a> {
+    /// Creates an iterator over the messages in some text
+    fn new(input: &'a str) -> ParseIter<'a> {
+        ParseIter { rest: input, line_number: 0 }
+    }
+
+    /// Returns the next line that is not blank, with the number of the line that it starts on
+    ///
+    /// Lines are joined as `split_record` describes, so the returned text may contain line
+    /// breaks. The line terminator at the end is removed, as `str::lines` does.
+    fn next_record(&mut self) -> Option<(usize, &'a str)> {
+        while let Some((record, length)) = split_record(self.rest, true) {
+            let line_number = self.line_number + 1;
+            self.line_number += self.rest[..length].matches('\n').count();
+            self.rest = &self.rest[length..];
+            if !record.trim().is_empty() {
+                return Some((line_number, record));
+            }
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for ParseIter<'a> {
+    type Item = Result<Message, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|(_, line)| parse(line))
+    }
+}
+
+/// Parses a line of text into a message, if it reports a position inside a box
+///
+/// The latitude and longitude are checked before the rest of the line is parsed, so lines
+/// outside the box are skipped quickly. Returns None if the line does not have a position, or
+/// its position is outside the box. The edges are inside the box.
+pub fn parse_in_box(message_string: &str, min_latitude: f64, max_latitude: f64,
+                    min_longitude: f64, max_longitude: f64) -> Result<Option<Message>, ParseError> {
+    let (fields, _) = split_fields(message_string, DEFAULT_DELIMITER, &DEFAULT_EXPECTED_FIELDS)?;
+    let in_box = match (fields[14].parse(), fields[15].parse()) {
+        (Ok(latitude), Ok(longitude)) => {
+            (min_latitude..=max_latitude).contains(&latitude) &&
+                (min_longitude..=max_longitude).contains(&longitude)
+        }
+        _ => false,
+    };
+    if !in_box {
+        return Ok(None);
+    }
+    parse(message_string).map(Some)
+}
+
+/// Parses a line of text into a message, using the provided options
+pub fn parse_with_options(message_string: &str, options: &ParseOptions) -> Result<Message, ParseError> {
+    parse_borrowed_with_options(message_string, DEFAULT_DELIMITER, options)
+        .map(|message| message.to_owned())
+}
+
+/// Parses a line of text with fields separated by a delimiter other than a comma
+///
+/// Some exports use semicolons or tabs instead of commas. The fields are otherwise the same.
+pub fn parse_with_delimiter(message_string: &str, delimiter: char) -> Result<Message, ParseError> {
+    parse_with_delimiter_and_options(message_string, delimiter, &ParseOptions::default())
+}
+
+/// Parses a line of text with fields separated by the provided delimiter, using the provided
+/// options
+pub fn parse_with_delimiter_and_options(message_string: &str, delimiter: char, options: &ParseOptions)
+                                        -> Result<Message, ParseError> {
+    parse_borrowed_with_options(message_string, delimiter, options).map(|message| message.to_owned())
+}
+
+/// Parses a line of text into a message that borrows its text fields from the line
+///
+/// This avoids the allocations that `parse` makes for every line.
+pub fn parse_borrowed<'a>(message_string: &'a str) -> Result<MessageRef<'a>, ParseError> {
+    parse_borrowed_with_options(message_string, DEFAULT_DELIMITER, &ParseOptions::default())
+}
+
+/// Parses a line of text into a borrowed message, using the provided options
+fn parse_borrowed_with_options<'a>(message_string: &'a str, delimiter: char, options: &ParseOptions)
+                                   -> Result<MessageRef<'a>, ParseError> {
+    // Fields that are missing from the end of a short line are empty
+    let (fields, _) = split_fields(message_string, delimiter, &options.expected_fields)?;
+    let parts = &fields;
+    if options.empty_message_type && parts[0].is_empty() {
+        return Err(ParseError::EmptyMessageType);
+    }
+    let message_type = parse_message_type(parts[0], parts[1])?;
+    // Create a message
+    let mut message = MessageRef::new(message_type);
+    // Fill in fields
+    message.session_id = parse_id(parts, 2, options)?;
+    message.aircraft_id = parse_id(parts, 3, options)?;
+    message.ident = parts[4].parse().ok();
+    message.icao_address = u32::from_str_radix(parts[4].trim(), 16).ok();
+    message.ident_raw = if parts[4].is_empty() { None } else { Some(parts[4]) };
+    message.flight_id = parse_id(parts, 5, options)?;
+    #[cfg(feature = "chrono")]
+    {
+        message.generated = parse_timestamp(parts[6], parts[7], options);
+        message.logged = parse_timestamp(parts[8], parts[9], options);
+    }
+    message.callsign = normalize_callsign(parts[10]);
+    if message.message_type == MessageType::StatusAircraft {
+        message.status = message.callsign.as_deref().and_then(AircraftStatus::from_code);
+    }
+    message.altitude = parse_numeric(parts, 11, options)?;
+    message.ground_speed = parse_numeric(parts, 12, options)?;
+    message.track = parse_numeric(parts, 13, options)?;
+    message.latitude = parse_numeric(parts, 14, options)?;
+    message.longitude = parse_numeric(parts, 15, options)?;
+    message.vertical_speed = parse_numeric(parts, 16, options)?;
+    if options.validate_squawk && !is_valid_squawk(parts[17]) {
+        return Err(ParseError::InvalidSquawk);
+    }
+    message.squawk = parse_numeric(parts, 17, options)?;
+    message.alert = parse_flag(parts[18]);
+    message.emergency = parse_flag(parts[19]);
+    message.special_position = parse_flag(parts[20]);
+    message.on_ground = parse_flag(parts[21]);
+    message.rssi = parse_numeric(parts, 22, options)?;
+    message.emitter_category = parse_emitter_category(parts, 23, options)?;
+    message.position_valid = parse_flag(parts[24]);
+    if options.keep_raw {
+        message.raw = Some(message_string);
+    }
+
+    Ok(message)
+}
+
+/// Parses the message type field and, for transmissions, the transmission type field
+///
+/// Whitespace around either field is ignored, and the transmission type may have leading zeros.
+fn parse_message_type(message_type: &str, transmission_type: &str) -> Result<MessageType, ParseError> {
+    match message_type.trim() {
+        "SEL" => Ok(MessageType::SelectionChange),
+        "ID" => Ok(MessageType::NewId),
+        "AIR" => Ok(MessageType::NewAircraft),
+        "STA" => Ok(MessageType::StatusAircraft),
+        "CLK" => Ok(MessageType::Click),
+        "MLAT" => Ok(MessageType::Mlat),
+        "MSG" => TransmissionType::try_from(transmission_type).map(MessageType::Transmission),
+        _ => Err(ParseError::InvalidMessageType),
+    }
+}
+
+/// Splits a line into its fields without allocating
+///
+/// Whitespace around each field is removed. Returns the fields and the number of fields in the
+/// line, or an error if the number of fields is not in the expected range.
+///
+/// Many feeds end each line with a delimiter. A line with every field is still valid if it
+/// has one more empty field at the end, and that field is ignored.
+///
+/// Fields may be quoted as in CSV, as described for `Fields`.
+fn split_fields<'a>(message_string: &'a str, delimiter: char, expected_fields: &Range<usize>)
+                    -> Result<([&'a str; EXTENDED_FIELD_COUNT], usize), ParseError> {
+    let mut fields = [""; EXTENDED_FIELD_COUNT];
+    let mut count = 0;
+    // A whitespace delimiter such as a tab must not be trimmed from the ends of the line,
+    // because that would drop empty fields
+    let is_padding = |c: char| c.is_whitespace() && c != delimiter;
+    let mut parts = Fields::new(message_string.trim_matches(is_padding), delimiter);
+    for part in &mut parts {
+        if count == EXTENDED_FIELD_COUNT {
+            if !part.trim_matches(is_padding).is_empty() || parts.next().is_some() {
+                return Err(ParseError::InvalidLineFormat);
+            }
+            break;
+        }
+        fields[count] = part.trim_matches(is_padding);
+        count += 1;
+    }
+    if !expected_fields.contains(&count) {
+        return Err(ParseError::InvalidLineFormat);
+    }
+    Ok((fields, count))
+}
+
+/// An iterator over the fields of a line, which are separated by a delimiter
+///
+/// A field that starts with a double quote, after any padding, continues until the matching
+/// closing quote, so it can contain delimiters and line breaks. Two double quotes in a row
+/// inside a quoted field are part of the field. The quotes are included in the field. If a
+/// quoted field has no closing quote, it ends at the next delimiter like any other field.
+struct Fields<'a> {
+    /// The text after the fields that have been returned, or None after the last field
+    rest: Option<&'a str>,
+    /// The character that separates the fields
+    delimiter: char,
+    /// True if a quoted field without a closing quote has been returned
+    unclosed_quote: bool,
+}
+
+impl<'a> Fields<'a> {
+    /// Creates an iterator over the fields of a line
+    fn new(line: &'a str, delimiter: char) -> Fields<'a> {
+        Fields { rest: Some(line), delimiter, unclosed_quote: false }
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.rest?;
+        let delimiter = self.delimiter;
+        let padding = rest.len() - rest.trim_start_matches(|c: char| c.is_whitespace() && c != delimiter).len();
+        // The delimiter is searched for after the closing quote of a quoted field
+        let mut search_from = 0;
+        if rest[padding..].starts_with('"') {
+            match closing_quote(&rest[padding + 1..]) {
+                Some(index) => search_from = padding + 1 + index + 1,
+                None => self.unclosed_quote = true,
+            }
+        }
+        match rest[search_from..].find(delimiter) {
+            Some(index) => {
+                let end = search_from + index;
+                self.rest = Some(&rest[end + delimiter.len_utf8()..]);
+                Some(&rest[..end])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Returns the index of the quote that ends a quoted field, given the text after its opening
+/// quote, or None if there is no closing quote
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut quotes = text.match_indices('"').peekable();
+    while let Some((index, _)) = quotes.next() {
+        match quotes.peek() {
+            // Two quotes in a row are an escaped quote
+            Some(&(next, _)) if next == index + 1 => {
+                quotes.next();
+            }
+            _ => return Some(index),
+        }
+    }
+    None
+}
+
+/// Returns true if a comma-separated line has a quoted field without a closing quote, which
+/// means that the field may continue on the next line
+fn has_unclosed_quote(line: &str) -> bool {
+    let mut fields = Fields::new(line, DEFAULT_DELIMITER);
+    fields.by_ref().for_each(drop);
+    fields.unclosed_quote
+}
+
+/// The most lines that one message can span, when a quoted field contains line breaks
+const MAX_RECORD_LINES: usize = 4;
+
+/// Finds the first message in some comma-separated text, and returns it with the length of the
+/// text that it takes up
+///
+/// A message is one line, or several lines if a quoted field contains line breaks. Lines are
+/// only joined while the message has at most `EXTENDED_FIELD_COUNT` delimiters and spans at
+/// most `MAX_RECORD_LINES` lines. Otherwise, the message is only the first line, and the quote
+/// that was not closed is read as part of its field, so one stray quote does not swallow the
+/// lines after it. The returned message does not include its line terminator.
+///
+/// If `complete` is false, more text may follow, and None is returned if the end of the
+/// message is not in the text yet. If it is true, the end of the text ends the last message,
+/// and None is only returned for empty text.
+fn split_record(text: &str, complete: bool) -> Option<(&str, usize)> {
+    // The end of the message and the start of the next one, if the message ended at each line
+    let line_ends = |from: usize| match text[from..].find('\n') {
+        Some(index) => Some((from + index, from + index + 1)),
+        None if complete && from < text.len() => Some((text.len(), text.len())),
+        None => None,
+    };
+    let first = line_ends(0)?;
+    let mut end = first;
+    let mut lines = 1;
+    while has_unclosed_quote(&text[..end.0]) {
+        let next = match line_ends(end.1) {
+            Some(next) => next,
+            None if complete => first,
+            None => return None,
+        };
+        lines += 1;
+        if next == first || lines > MAX_RECORD_LINES ||
+            text[..next.0].matches(DEFAULT_DELIMITER).count() > EXTENDED_FIELD_COUNT {
+            end = first;
+            break;
+        }
+        end = next;
+    }
+    let record = &text[..end.0];
+    Some((record.strip_suffix('\r').unwrap_or(record), end.1))
+}
+
+/// Parses an emitter category field, such as A3, into a number with the set letter as the high
+/// four bits and the category number as the low four bits
+///
+/// Errors are handled in the same way as `parse_numeric`.
+fn parse_emitter_category(parts: &[&str], index: usize, options: &ParseOptions)
+                          -> Result<Option<u8>, ParseError> {
+    let part = parts[index];
+    let mut chars = part.chars();
+    let valid = match (chars.next(), chars.next(), chars.next()) {
+        (Some(set), Some(number), None) => {
+            ('A'..='D').contains(&set.to_ascii_uppercase()) && ('0'..='7').contains(&number)
+        }
+        _ => false,
+    };
+    match u8::from_str_radix(part, 16) {
+        Ok(value) if valid => Ok(Some(value)),
+        _ if options.strict_numeric && !part.is_empty() => {
+            Err(ParseError::FieldError { index, field: FIELD_NAMES[index] })
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parses a session, aircraft, or flight ID field
+///
+/// A field that cannot be parsed is None. If ID range checking is enabled, a whole number that
+/// does not fit in a u32 is an error instead.
+fn parse_id(parts: &[&str], index: usize, options: &ParseOptions) -> Result<Option<u32>, ParseError> {
+    let part = parts[index];
+    match part.parse() {
+        Ok(id) => Ok(Some(id)),
+        Err(_) if options.check_id_range && part.parse::<i128>().is_ok() => {
+            Err(ParseError::NumericOverflow { field: FIELD_NAMES[index] })
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parses a numeric field
+///
+/// An empty field is None. A field that cannot be parsed is also None, unless strict numeric
+/// parsing is enabled.
+fn parse_numeric<T: FromStr>(parts: &[&str], index: usize, options: &ParseOptions)
+                             -> Result<Option<T>, ParseError> {
+    let part = parts[index];
+    let number = if options.numeric_strip_nonnumeric {
+        part.trim_end_matches(|c: char| !c.is_ascii_digit())
+    } else {
+        part
+    };
+    let parsed = if options.decimal_comma && number.contains(',') {
+        number.replace(',', ".").parse()
+    } else {
+        number.parse()
+    };
+    match parsed {
+        Ok(value) => Ok(Some(value)),
+        Err(_) if options.strict_numeric && !part.is_empty() => {
+            Err(ParseError::FieldError { index, field: FIELD_NAMES[index] })
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parses a flag field
+///
+/// BaseStation uses -1 for true and 0 for false, and other receivers use 1 for true. The
+/// words true and false are also accepted. Any other value is None.
+fn parse_flag(flag: &str) -> Option<bool> {
+    match flag {
+        "-1" | "1" => Some(true),
+        "0" => Some(false),
+        _ => flag.parse().ok(),
+    }
+}
+
+/// Removes the padding from a callsign, returning None if nothing is left
+///
+/// Callsigns are padded to 8 characters with spaces, and some receivers pad with @ or _
+/// instead. All of these are removed from the end of the callsign, including runs that mix
+/// different padding characters, as well as any leading whitespace.
+///
+/// Some exporters wrap the callsign in double quotes. A matched pair of quotes around the
+/// callsign is removed before the padding, and two quotes in a row inside them are read as one
+/// quote, as in CSV. The callsign is only copied if it has such quotes.
+fn normalize_callsign(callsign: &str) -> Option<Cow<'_, str>> {
+    let callsign = callsign.trim();
+    let quoted = callsign.len() >= 2 && callsign.starts_with('"') && callsign.ends_with('"');
+    let callsign = if quoted { &callsign[1..callsign.len() - 1] } else { callsign };
+    let callsign = callsign.trim_start()
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '@' || c == '_');
+    if callsign.is_empty() {
+        None
+    } else if quoted && callsign.contains("\"\"") {
+        Some(Cow::Owned(callsign.replace("\"\"", "\"")))
+    } else {
+        Some(Cow::Borrowed(callsign))
+    }
+}
+
+/// Checks that a squawk field is empty or contains at most four octal digits
+fn is_valid_squawk(squawk: &str) -> bool {
+    squawk.len() <= 4 && squawk.chars().all(|c| ('0'..='7').contains(&c))
+}
+
+#[cfg(test)]
+mod core_tests {
+    //! Tests that do not need the std or chrono features
+    use super::*;
+
+    #[test]
+    fn test_parse_transmission() {
+        let line = "MSG,3,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,\
+                    DLH123,35000,,,47.65,-122.30825,,,,,,";
+        let message = parse(line).unwrap();
+        assert_eq!(MessageType::Transmission(TransmissionType::EsAirbornePos), message.message_type);
+        assert_eq!(Some(0xA1B2C3), message.icao_address);
+        assert_eq!(Some(String::from("DLH123")), message.callsign);
+        assert_eq!(Some(35000.0), message.altitude);
+        assert_eq!(Some(-122.30825), message.longitude);
+        assert_eq!(Ok(message.clone()), parse(&message.to_sbs1_string()));
+    }
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(Err(ParseError::InvalidLineFormat), parse("MSG,3"));
+        assert_eq!(Err(ParseError::InvalidMessageType), parse("XYZ,,,,,,,,,,,,,,,,,,,,,"));
+    }
+    #[test]
+    fn test_type_tokens() {
+        let tokens = MessageType::all_base().iter().map(MessageType::type_token).collect::<Vec<_>>();
+        assert_eq!(vec!["SEL", "ID", "AIR", "STA", "CLK", "MLAT"], tokens);
+        for message_type in MessageType::all_base() {
+            assert_eq!(None, Message::new(message_type.clone()).transmission_code_str());
+        }
+        for (index, transmission_type) in TransmissionType::all().iter().enumerate() {
+            let message_type = MessageType::Transmission(transmission_type.clone());
+            assert_eq!("MSG", message_type.type_token());
+            let code = Message::new(message_type).transmission_code_str().unwrap();
+            assert_eq!(transmission_type.code().to_string(), code);
+            assert_eq!((index + 1).to_string(), code);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use chrono::offset::local::Local;
+    use chrono::offset::utc::UTC;
+    use chrono::offset::TimeZone;
+    use super::*;
+    #[test]
+    fn test_empty_string() {
+        let result = parse("");
+        assert_eq!(Err(ParseError::InvalidLineFormat), result);
+    }
+    #[test]
+    fn test_few_commas() {
+        let result = parse(",,,,");
+        assert_eq!(Err(ParseError::InvalidLineFormat), result);
+    }
+    #[test]
+    fn test_correct_commas_empty() {
+        let result = parse(",,,,,,,,,,,,,,,,,,,,,");
+        assert_eq!(Err(ParseError::InvalidMessageType), result);
+    }
+    #[test]
+    fn test_selection_change() {
+        let result = parse("SEL,,,,,,,,,,,,,,,,,,,,,");
+        let expected = Ok(Message::new(MessageType::SelectionChange));
+        assert_eq!(expected, result);
+    }
+    #[test]
+    #[allow(clippy::nonminimal_bool)]
+    fn test_selection_change_vertical_speed() {
+        let result = parse("SEL,,,,,,,,,,,,,,,,-350,,,,,");
+        assert!(!result.is_err());
+        let result = result.unwrap();
+        assert_eq!(MessageType::SelectionChange, result.message_type);
+        assert!(result.vertical_speed.is_some());
+        assert_eq!(-350_f64, result.vertical_speed.unwrap());
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn test_date_time_parse() {
+        let date = "2016/03/11";
+        let time = "21:24:53.351";
+        let result = super::parse_date_time(date, time);
+        match result {
+            Ok(_) => {},
+            Err(e) => {
+                println!("{:?}", e);
+                assert!(false);
+            },
+        }
+    }
+
+    /// Asserts that a message is unchanged after converting it to text and parsing it again
+    fn assert_round_trip(message: Message) {
+        let line = message.to_sbs1_string();
+        assert_eq!(Ok(message), parse(&line), "line: {}", line);
+    }
+
+    #[test]
+    fn test_round_trip_message_types() {
+        let types = vec![
+            MessageType::SelectionChange,
+            MessageType::NewId,
+            MessageType::NewAircraft,
+            MessageType::StatusAircraft,
+            MessageType::Click,
+            MessageType::Mlat,
+            MessageType::Transmission(TransmissionType::EsIdentAndCategory),
+            MessageType::Transmission(TransmissionType::EsSurfacePos),
+            MessageType::Transmission(TransmissionType::EsAirbornePos),
+            MessageType::Transmission(TransmissionType::EsAirborneVel),
+            MessageType::Transmission(TransmissionType::SurveillanceAlt),
+            MessageType::Transmission(TransmissionType::SurveillanceId),
+            MessageType::Transmission(TransmissionType::AirToAir),
+            MessageType::Transmission(TransmissionType::AllCallReply),
+        ];
+        for message_type in types {
+            assert_round_trip(Message::new(message_type));
+        }
+    }
+    #[test]
+    fn test_empty_message_string() {
+        let message = Message::new(MessageType::Transmission(TransmissionType::AirToAir));
+        assert_eq!("MSG,7,,,,,,,,,,,,,,,,,,,,", message.to_sbs1_string());
+    }
+    #[test]
+    fn test_round_trip_populated_transmissions() {
+        let mut position = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        position.session_id = Some(111);
+        position.aircraft_id = Some(11111);
+        position.ident = Some(123456);
+        position.icao_address = Some(0x123456);
+        position.ident_raw = Some(String::from("123456"));
+        position.flight_id = Some(111111);
+        position.generated = parse_date_time("2016/03/11", "21:24:53.351").ok();
+        position.logged = parse_date_time("2016/03/11", "21:24:53.400").ok();
+        position.altitude = Some(35000.0);
+        position.latitude = Some(47.65);
+        position.longitude = Some(-122.30825);
+        position.alert = Some(false);
+        position.emergency = Some(false);
+        position.special_position = Some(true);
+        position.on_ground = Some(false);
+        assert_round_trip(position);
+
+        let mut velocity = Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel));
+        velocity.ground_speed = Some(451.5);
+        velocity.track = Some(123.4);
+        velocity.vertical_speed = Some(-1088.0);
+        velocity.generated = parse_date_time("2016/03/11", "21:24:54.002").ok();
+        assert_round_trip(velocity);
+
+        let mut ident = Message::new(MessageType::Transmission(TransmissionType::SurveillanceId));
+        ident.icao_address = Some(0xA1B2C3);
+        ident.ident_raw = Some(String::from("A1B2C3"));
+        ident.callsign = Some(String::from("DLH123"));
+        ident.squawk = Some(7000);
+        ident.alert = Some(true);
+        assert_round_trip(ident);
+    }
+    #[test]
+    fn test_round_trip_builder() {
+        let built = MessageBuilder::new(MessageType::Transmission(TransmissionType::EsAirbornePos))
+            .icao_address(0x123456)
+            .altitude(35000.0)
+            .build();
+        let line = built.to_sbs1_string();
+        let parsed = parse(&line).unwrap();
+        // The identifiers are filled in from the HexIdent column
+        assert_eq!(Some(0x123456), parsed.icao_address);
+        assert_eq!(Some(String::from("123456")), parsed.ident_raw);
+        assert_eq!(Some(123456), parsed.ident);
+        assert_ne!(built, parsed);
+        assert_eq!(Message { ident: Some(123456), ident_raw: Some(String::from("123456")), ..built },
+                   parsed);
+        assert_eq!(line, parsed.to_sbs1_string());
+    }
+
+    #[test]
+    fn test_icao_address() {
+        let result = parse("MSG,3,,,A1B2C3,,,,,,,,,,,,,,,,,").unwrap();
+        assert_eq!(Some(0x00A1B2C3), result.icao_address);
+        assert_eq!(None, result.ident);
+        assert_eq!(Some(String::from("A1B2C3")), result.icao_hex());
+    }
+    #[test]
+    fn test_icao_hex_padding() {
+        let mut message = Message::new(MessageType::NewAircraft);
+        assert_eq!(None, message.icao_hex());
+        message.icao_address = Some(0x00AB12);
+        assert_eq!(Some(String::from("00AB12")), message.icao_hex());
+    }
+
+    #[test]
+    fn test_lenient_numeric() {
+        let result = parse("MSG,3,,,,,,,,,,not_a_number,,,,,,,,,,").unwrap();
+        assert_eq!(None, result.altitude);
+    }
+    #[test]
+    fn test_strict_numeric() {
+        let options = ParseOptions { strict_numeric: true, ..ParseOptions::default() };
+        let cases = vec![
+            ("MSG,3,,,,,,,,,,not_a_number,,,,,,,,,,", 11, "altitude"),
+            ("MSG,3,,,,,,,,,,,,,not_a_number,,,,,,,", 14, "latitude"),
+            ("MSG,3,,,,,,,,,,,,,,not_a_number,,,,,,", 15, "longitude"),
+            ("MSG,6,,,,,,,,,,,,,,,,not_a_number,,,,", 17, "squawk"),
+        ];
+        for (line, index, field) in cases {
+            let result = parse_with_options(line, &options);
+            assert_eq!(Err(ParseError::FieldError { index, field }), result);
+        }
+    }
+    #[test]
+    fn test_strict_numeric_empty() {
+        let options = ParseOptions { strict_numeric: true, ..ParseOptions::default() };
+        let result = parse_with_options("MSG,3,,,,,,,,,,,,,,,,,,,,", &options).unwrap();
+        assert_eq!(None, result.altitude);
+    }
+    #[test]
+    fn test_field_error_display() {
+        let error = ParseError::FieldError { index: 14, field: "latitude" };
+        assert_eq!("Parse error: Invalid value in column 14 (latitude)", error.to_string());
+    }
+
+    #[test]
+    fn test_validate_squawk() {
+        let options = ParseOptions { validate_squawk: true, ..ParseOptions::default() };
+        let line = |squawk: &str| format!("MSG,6,,,,,,,,,,,,,,,,{},,,,", squawk);
+
+        let result = parse_with_options(&line("7700"), &options).unwrap();
+        assert_eq!(Some(7700), result.squawk);
+        let result = parse_with_options(&line("7777"), &options).unwrap();
+        assert_eq!(Some(7777), result.squawk);
+        let result = parse_with_options(&line(""), &options).unwrap();
+        assert_eq!(None, result.squawk);
+        assert_eq!(Err(ParseError::InvalidSquawk), parse_with_options(&line("8000"), &options));
+        assert_eq!(Err(ParseError::InvalidSquawk), parse_with_options(&line("77770"), &options));
+    }
+    #[test]
+    fn test_lenient_squawk() {
+        let result = parse("MSG,6,,,,,,,,,,,,,,,,8000,,,,").unwrap();
+        assert_eq!(Some(8000), result.squawk);
+    }
+
+    #[test]
+    fn test_emergency_kind() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::SurveillanceId));
+        assert_eq!(None, message.emergency_kind());
+        message.squawk = Some(7500);
+        assert_eq!(Some(EmergencyKind::Hijack), message.emergency_kind());
+        message.squawk = Some(7600);
+        assert_eq!(Some(EmergencyKind::RadioFailure), message.emergency_kind());
+        message.squawk = Some(7700);
+        message.emergency = Some(false);
+        assert_eq!(Some(EmergencyKind::GeneralEmergency), message.emergency_kind());
+        message.squawk = Some(1200);
+        assert_eq!(None, message.emergency_kind());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.session_id = Some(1);
+        message.icao_address = Some(0xA1B2C3);
+        message.generated = parse_date_time("2016/03/11", "21:24:53.351").ok();
+        message.logged = parse_date_time("2016/03/11", "21:24:53.400").ok();
+        message.callsign = Some(String::from("DLH123"));
+        message.altitude = Some(35000.0);
+        message.latitude = Some(47.65);
+        message.longitude = Some(-122.30825);
+        message.on_ground = Some(false);
+
+        let json = serde_json::to_string(&message).unwrap();
+        let expected_generated = format!("\"generated\":\"{}\"",
+                                         message.generated.unwrap().to_rfc3339());
+        assert!(json.contains(&expected_generated), "JSON: {}", json);
+        let deserialized: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(message, deserialized);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_parse_error() {
+        let error = ParseError::FieldError { index: 11, field: "altitude" };
+        let json = serde_json::to_string(&error).unwrap();
+        let deserialized: ParseError = serde_json::from_str(&json).unwrap();
+        assert_eq!(error, deserialized);
+    }
+
+    #[test]
+    fn test_utc_timestamps() {
+        let options = ParseOptions { utc_timestamps: true, ..ParseOptions::default() };
+        let line = "MSG,3,,,,,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,,,,,,,,,,,";
+        let result = parse_with_options(line, &options).unwrap();
+        assert_eq!(Some(UTC.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 351)), result.generated_utc());
+        assert_eq!(Some(UTC.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 400)), result.logged_utc());
+    }
+    #[test]
+    fn test_local_timestamps_to_utc() {
+        let line = "MSG,3,,,,,2016/03/11,21:24:53.351,,,,,,,,,,,,,,";
+        let result = parse(line).unwrap();
+        let expected = Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 351).with_timezone(&UTC);
+        assert_eq!(Some(expected), result.generated_utc());
+        assert_eq!(None, result.logged_utc());
+    }
+
+    #[test]
+    fn test_altitude_conversions() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert_eq!(None, message.altitude_meters());
+        assert_eq!(None, message.altitude_flight_level());
+        message.altitude = Some(35000.0);
+        assert!((message.altitude_meters().unwrap() - 10668.0).abs() < 1e-6);
+        assert_eq!(Some(350), message.altitude_flight_level());
+        message.altitude = Some(34960.0);
+        assert_eq!(Some(350), message.altitude_flight_level());
+    }
+
+    #[test]
+    fn test_speed_conversions() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel));
+        assert_eq!(None, message.ground_speed_kmh());
+        assert_eq!(None, message.ground_speed_ms());
+        assert_eq!(None, message.vertical_speed_ms());
+        message.ground_speed = Some(400.0);
+        message.vertical_speed = Some(1000.0);
+        assert!((message.ground_speed_kmh().unwrap() - 740.8).abs() < 1e-9);
+        assert!((message.ground_speed_ms().unwrap() - 205.7776).abs() < 1e-9);
+        assert!((message.vertical_speed_ms().unwrap() - 5.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert_eq!(Ok(()), message.validate());
+        message.latitude = Some(47.65);
+        message.longitude = Some(-122.30825);
+        message.track = Some(0.0);
+        message.altitude = Some(35000.0);
+        assert_eq!(Ok(()), message.validate());
+
+        let mut bad_latitude = message.clone();
+        bad_latitude.latitude = Some(91.0);
+        assert_eq!(Err(ValidationError::LatitudeOutOfRange), bad_latitude.validate());
+        let mut bad_longitude = message.clone();
+        bad_longitude.longitude = Some(-200.0);
+        assert_eq!(Err(ValidationError::LongitudeOutOfRange), bad_longitude.validate());
+        let mut bad_track = message.clone();
+        bad_track.track = Some(360.0);
+        assert_eq!(Err(ValidationError::TrackOutOfRange), bad_track.validate());
+        let mut bad_altitude = message.clone();
+        bad_altitude.altitude = Some(150000.0);
+        assert_eq!(Err(ValidationError::AltitudeOutOfRange), bad_altitude.validate());
+    }
+
+    #[test]
+    fn test_rssi() {
+        let result = parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, result.rssi);
+        let result = parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,,-12.5").unwrap();
+        assert_eq!(Some(35000.0), result.altitude);
+        assert_eq!(Some(-12.5), result.rssi);
+        assert_round_trip(result);
+        let result = parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,,").unwrap();
+        assert_eq!(None, result.rssi);
+    }
+    #[test]
+    fn test_too_many_fields() {
+        let result = parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,,-12.5,A3,-1,X");
+        assert_eq!(Err(ParseError::InvalidLineFormat), result);
+    }
+
+    #[test]
+    fn test_date_time_parse_formats() {
+        let fractional = parse_date_time("2016/03/11", "21:24:53.351").unwrap();
+        assert_eq!(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 351), fractional);
+        let whole = parse_date_time("2016/03/11", "21:24:53").unwrap();
+        assert_eq!(Local.ymd(2016, 3, 11).and_hms(21, 24, 53), whole);
+        assert!(parse_date_time("2016/03/11", "21:24").is_err());
+    }
+    #[test]
+    fn test_round_trip_whole_seconds() {
+        let mut message = Message::new(MessageType::NewAircraft);
+        message.generated = Some(Local.ymd(2016, 3, 11).and_hms(21, 24, 53));
+        assert_round_trip(message);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let line = "MSG,3,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,\
+                    35000,,,47.65,-122.30825,,,0,0,0,0";
+        let message: Message = line.parse().unwrap();
+        assert_eq!(parse(line), Ok(message.clone()));
+        assert_eq!(Some(0xA1B2C3), message.icao_address);
+        assert_eq!(Some(47.65), message.latitude);
+        let result: Result<Message, ParseError> = "MSG,9,,,,,,,,,,,,,,,,,,,,".parse();
+        assert_eq!(Err(ParseError::InvalidTransmissionType), result);
+    }
+
+    #[test]
+    fn test_message_type_display() {
+        assert_eq!("Selection Change", MessageType::SelectionChange.to_string());
+        assert_eq!("New ID", MessageType::NewId.to_string());
+        assert_eq!("New Aircraft", MessageType::NewAircraft.to_string());
+        assert_eq!("Aircraft Status", MessageType::StatusAircraft.to_string());
+        assert_eq!("Click", MessageType::Click.to_string());
+        assert_eq!("Multilateration", MessageType::Mlat.to_string());
+        assert_eq!("Transmission (ES Airborne Position)",
+                   MessageType::Transmission(TransmissionType::EsAirbornePos).to_string());
+    }
+    #[test]
+    fn test_transmission_type_display() {
+        assert_eq!("ES Identification and Category", TransmissionType::EsIdentAndCategory.to_string());
+        assert_eq!("ES Surface Position", TransmissionType::EsSurfacePos.to_string());
+        assert_eq!("ES Airborne Position", TransmissionType::EsAirbornePos.to_string());
+        assert_eq!("ES Airborne Velocity", TransmissionType::EsAirborneVel.to_string());
+        assert_eq!("Surveillance Altitude", TransmissionType::SurveillanceAlt.to_string());
+        assert_eq!("Surveillance ID", TransmissionType::SurveillanceId.to_string());
+        assert_eq!("Air To Air", TransmissionType::AirToAir.to_string());
+        assert_eq!("All Call Reply", TransmissionType::AllCallReply.to_string());
+    }
+
+    #[test]
+    fn test_transmission_codes() {
+        for code in 1..9 {
+            let transmission_type = TransmissionType::from_code(code).unwrap();
+            assert_eq!(code, transmission_type.code());
+        }
+        assert_eq!(Some(TransmissionType::AirToAir), TransmissionType::from_code(7));
+        assert_eq!(None, TransmissionType::from_code(0));
+        assert_eq!(None, TransmissionType::from_code(9));
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let line = "MSG,4,,,A1B2C3,,,,,,,,xyz,180.0,,,,,,,,";
+        assert_eq!(None, parse(line).unwrap().ground_speed);
+        assert_eq!(vec![12], Message::parse_warnings(line));
+        let line = "MSG,4,,,A1B2C3,,2016/03/11,bad,,,,,400,180.0,,,,,,,,";
+        assert_eq!(vec![6, 7], Message::parse_warnings(line));
+        assert!(Message::parse_warnings("MSG,4,,,,,,,,,,,400,,,,,,,,,").is_empty());
+        assert!(Message::parse_warnings("MSG,4,,,").is_empty());
+    }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let line = "MSG,1,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,\
+                    DLH123  ,35000,451.5,123.4,47.65,-122.30825,-1088,7000,false,false,false,false";
+        let borrowed = parse_borrowed(line).unwrap();
+        assert_eq!(MessageType::Transmission(TransmissionType::EsIdentAndCategory),
+                   borrowed.message_type);
+        assert_eq!(Some(0xA1B2C3), borrowed.icao_address);
+        assert_eq!(Some("DLH123"), borrowed.callsign.as_deref());
+        // The callsign refers to the original line
+        let line_range = line.as_ptr() as usize..line.as_ptr() as usize + line.len();
+        assert!(line_range.contains(&(borrowed.callsign.as_deref().unwrap().as_ptr() as usize)));
+        assert_eq!(Some(-1088.0), borrowed.vertical_speed);
+        assert_eq!(parse(line), Ok(borrowed.to_owned()));
+    }
+    #[test]
+    fn test_parse_borrowed_many() {
+        let lines = [
+            "SEL,,,,,,,,,,,,,,,,,,,,,",
+            "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,",
+            "MSG,4,,,A1B2C3,,,,,,,,451.5,123.4,,,-1088,,,,,,-10.5",
+            "MSG,6,,,A1B2C3,,,,,,DLH123,,,,,,,7000,,,,",
+            "MSG,9,,,,,,,,,,,,,,,,,,,,",
+            "MSG,3,,,",
+        ];
+        for _ in 0..1000 {
+            for line in lines.iter() {
+                let borrowed = parse_borrowed(line).map(|message| message.to_owned());
+                assert_eq!(parse(line), borrowed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_position_report() {
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,\
+                    35000,,,47.65,-122.30825,,,,,,";
+        let first = parse(line).unwrap();
+        let mut second = first.clone();
+        second.generated = parse_date_time("2016/03/11", "21:24:53.352").ok();
+        assert!(first != second);
+        assert!(first.same_position_report(&second));
+
+        let mut moved = second.clone();
+        moved.latitude = Some(47.66);
+        assert!(!first.same_position_report(&moved));
+        let mut other_aircraft = second.clone();
+        other_aircraft.icao_address = Some(0xA1B2C4);
+        assert!(!first.same_position_report(&other_aircraft));
+
+        let no_position = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert!(!no_position.same_position_report(&no_position.clone()));
+    }
+
+    #[test]
+    fn test_mlat() {
+        let line = "MLAT,3,1,1,A1B2C3,1,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,\
+                    35000,451.5,123.4,47.65,-122.30825,-1088,,,,,";
+        let result = parse(line).unwrap();
+        assert_eq!(MessageType::Mlat, result.message_type);
+        assert_eq!(Some(0xA1B2C3), result.icao_address);
+        assert_eq!(Some(35000.0), result.altitude);
+        assert_eq!(Some(451.5), result.ground_speed);
+        assert_eq!(Some(47.65), result.latitude);
+        assert_eq!(Some(-122.30825), result.longitude);
+        assert!(result.generated.is_some());
+    }
+
+    #[test]
+    fn test_callsign_padding() {
+        let line = |callsign: &str| format!("MSG,1,,,A1B2C3,,,,,,{},,,,,,,,,,,", callsign);
+        assert_eq!(Some(String::from("DLH2AB")), parse(&line("DLH2AB @")).unwrap().callsign);
+        assert_eq!(Some(String::from("DLH2AB")), parse(&line("DLH2AB@@")).unwrap().callsign);
+        assert_eq!(Some(String::from("UAL1")), parse(&line("UAL1 _@ ")).unwrap().callsign);
+        assert_eq!(Some(String::from("N123AB")), parse(&line(" N123AB")).unwrap().callsign);
+        assert_eq!(None, parse(&line("@@@@@@@@")).unwrap().callsign);
+        assert_eq!(None, parse(&line("        ")).unwrap().callsign);
+    }
+    #[test]
+    fn test_quoted_callsign() {
+        let line = |callsign: &str| format!("MSG,1,,,A1B2C3,,,,,,{},,,,,,,,,,,", callsign);
+        assert_eq!(Some(String::from("DLH123")), parse(&line("\"DLH123\"")).unwrap().callsign);
+        assert_eq!(Some(String::from("DLH123")), parse(&line("\"DLH123  \"")).unwrap().callsign);
+        assert_eq!(None, parse(&line("\"\"")).unwrap().callsign);
+        // Unmatched quotes are kept
+        assert_eq!(Some(String::from("\"DLH123")), parse(&line("\"DLH123")).unwrap().callsign);
+        assert_eq!(Some(String::from("\"")), parse(&line("\"")).unwrap().callsign);
+        // Quotes in a quoted callsign are doubled when writing, and undoubled when parsing
+        for callsign in &["A\",B", "A\"B", "\"DLH123\"", "\"", "DLH\n\"1\""] {
+            let mut message = parse(&line("")).unwrap();
+            message.callsign = Some(String::from(*callsign));
+            assert_eq!(message, parse(&message.to_sbs1_string()).unwrap());
+        }
+        assert!(Message { callsign: Some(String::from("A\",B")), ..parse(&line("")).unwrap() }
+            .to_sbs1_string().contains(",\"A\"\",B\","));
+        // Doubled quotes are only special inside quotes
+        assert_eq!(Some(String::from("A\"\"B")), parse(&line("A\"\"B")).unwrap().callsign);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_geojson_feature() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert_eq!(None, message.to_geojson_feature());
+        message.latitude = Some(47.65);
+        assert_eq!(None, message.to_geojson_feature());
+        message.longitude = Some(-122.30825);
+        message.altitude = Some(35000.0);
+        message.icao_address = Some(0xA1B2C3);
+
+        let feature = message.to_geojson_feature().unwrap();
+        assert_eq!("Feature", feature["type"]);
+        assert_eq!("Point", feature["geometry"]["type"]);
+        assert_eq!(json!([-122.30825, 47.65]), feature["geometry"]["coordinates"]);
+        assert_eq!(35000.0, feature["properties"]["altitude"]);
+        assert_eq!("A1B2C3", feature["properties"]["icao_hex"]);
+        assert!(feature["properties"]["callsign"].is_null());
+    }
+
+    #[test]
+    fn test_csv_row() {
+        let empty = Message::new(MessageType::SelectionChange);
+        let header_columns = Message::csv_header().split(',').count();
+        assert_eq!(header_columns, empty.to_csv_row().split(',').count());
+        assert_eq!(empty.fields().len(), header_columns);
+        assert_eq!("Selection Change,,,,,,,,,,,,,,,,,,,,,,,,", empty.to_csv_row());
+
+        let line = "MSG,3,111,11111,A1B2C3,111111,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,\
+                    DLH123,35000,451.5,123.4,47.65,-122.30825,-1088,7000,false,false,false,true";
+        let message = parse(line).unwrap();
+        let row = message.to_csv_row();
+        let columns = row.split(',').collect::<Vec<_>>();
+        assert_eq!(header_columns, columns.len());
+        let names = message.fields().iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, Message::csv_header().split(',').collect::<Vec<_>>());
+        assert_eq!("A1B2C3", columns[4]);
+        assert_eq!("A1B2C3", columns[5]);
+        assert_eq!(message.generated.unwrap().to_rfc3339(), columns[7]);
+        assert_eq!("DLH123", columns[9]);
+        assert_eq!("true", columns[20]);
+        assert_eq!("", columns[21]);
+        let mut extended = message.clone();
+        extended.emitter_category = Some(0xA3);
+        extended.position_valid = Some(true);
+        extended.status = Some(AircraftStatus::Ok);
+        assert!(extended.to_csv_row().ends_with(",,A3,true,OK"));
+    }
+    #[test]
+    fn test_escape_csv() {
+        assert_eq!("DLH123", escape_csv("DLH123"));
+        assert_eq!("\"DLH,123\"", escape_csv("DLH,123"));
+        assert_eq!("\"D\"\"LH\"", escape_csv("D\"LH"));
+    }
+
+    /// Creates a position message at the provided coordinates
+    fn position_at(latitude: f64, longitude: f64) -> Message {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.latitude = Some(latitude);
+        message.longitude = Some(longitude);
+        message
+    }
+
+    #[test]
+    fn test_distance_to() {
+        // Nashville International Airport to Los Angeles International Airport
+        let nashville = position_at(36.12, -86.67);
+        let los_angeles = position_at(33.94, -118.40);
+        assert!((nashville.distance_to(&los_angeles).unwrap() - 2886.44).abs() < 1.0);
+        assert!((los_angeles.distance_to(&nashville).unwrap() - 2886.44).abs() < 1.0);
+        // London Heathrow to Paris Charles de Gaulle
+        let heathrow = position_at(51.4700, -0.4543);
+        let charles_de_gaulle = position_at(49.0097, 2.5479);
+        assert!((heathrow.distance_to(&charles_de_gaulle).unwrap() - 347.0).abs() < 1.0);
+        assert_eq!(Some(0.0), heathrow.distance_to(&heathrow));
+
+        let no_position = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert_eq!(None, heathrow.distance_to(&no_position));
+        assert_eq!(None, no_position.distance_to(&heathrow));
+    }
+    #[test]
+    fn test_bearing_to() {
+        let origin = position_at(0.0, 0.0);
+        assert!((origin.bearing_to(&position_at(1.0, 0.0)).unwrap() - 0.0).abs() < 1e-9);
+        assert!((origin.bearing_to(&position_at(0.0, 1.0)).unwrap() - 90.0).abs() < 1e-9);
+        assert!((origin.bearing_to(&position_at(-1.0, 0.0)).unwrap() - 180.0).abs() < 1e-9);
+        assert!((origin.bearing_to(&position_at(0.0, -1.0)).unwrap() - 270.0).abs() < 1e-9);
+        let nashville = position_at(36.12, -86.67);
+        let los_angeles = position_at(33.94, -118.40);
+        assert!((nashville.bearing_to(&los_angeles).unwrap() - 274.59).abs() < 0.01);
+        let no_position = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        assert_eq!(None, origin.bearing_to(&no_position));
+    }
+
+    #[test]
+    fn test_whitespace_in_fields() {
+        let line = "MSG,3, 111 ,,A1B2C3 ,,,,,,, 35000,,\t123.4,47.65 , -122.30825,,7000 ,, true,,";
+        let result = parse(line).unwrap();
+        assert_eq!(Some(111), result.session_id);
+        assert_eq!(Some(0xA1B2C3), result.icao_address);
+        assert_eq!(Some(35000.0), result.altitude);
+        assert_eq!(Some(123.4), result.track);
+        assert_eq!(Some(47.65), result.latitude);
+        assert_eq!(Some(-122.30825), result.longitude);
+        assert_eq!(Some(7000), result.squawk);
+        assert_eq!(Some(true), result.emergency);
+        assert!(Message::parse_warnings(line).is_empty());
+    }
+    #[test]
+    fn test_crlf_line() {
+        let result = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,\r\n").unwrap();
+        assert_eq!(Some(35000.0), result.altitude);
+        assert_eq!(None, result.on_ground);
+    }
+
+    #[test]
+    fn test_transmission_type_accessors() {
+        let transmission = MessageType::Transmission(TransmissionType::EsAirbornePos);
+        assert!(transmission.is_transmission());
+        assert_eq!(Some(&TransmissionType::EsAirbornePos), transmission.transmission_type());
+        let selection = MessageType::SelectionChange;
+        assert!(!selection.is_transmission());
+        assert_eq!(None, selection.transmission_type());
+        assert!(!MessageType::Mlat.is_transmission());
+    }
+
+    #[test]
+    fn test_flags() {
+        let line = |flag: &str| format!("MSG,3,,,,,,,,,,,,,,,,,{0},{0},{0},{0}", flag);
+        for &(flag, expected) in [("0", Some(false)), ("1", Some(true)), ("-1", Some(true)),
+                                  ("", None), ("true", Some(true)), ("2", None)].iter() {
+            let result = parse(&line(flag)).unwrap();
+            assert_eq!(expected, result.alert, "flag: {}", flag);
+            assert_eq!(expected, result.emergency, "flag: {}", flag);
+            assert_eq!(expected, result.special_position, "flag: {}", flag);
+            assert_eq!(expected, result.on_ground, "flag: {}", flag);
+        }
+    }
+    #[test]
+    fn test_write_flags() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.alert = Some(true);
+        message.emergency = Some(false);
+        assert_eq!("MSG,3,,,,,,,,,,,,,,,,,-1,0,,", message.to_sbs1_string());
+    }
+
+    #[test]
+    fn test_merge() {
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,47.65,-122.30825,,,,,,";
+        let mut state = parse(line).unwrap();
+        let line = "MSG,1,,,A1B2C3,,2016/03/11,21:24:52.000,,,DLH123,,,,,,,,,,,";
+        let callsign = parse(line).unwrap();
+        state.merge(&callsign);
+        assert_eq!(Some(String::from("DLH123")), state.callsign);
+        assert_eq!(Some(35000.0), state.altitude);
+        assert_eq!(Some(47.65), state.latitude);
+        // The callsign message is older, so the time is not changed
+        assert_eq!(parse_date_time("2016/03/11", "21:24:53.351").ok(), state.generated);
+        assert_eq!(MessageType::Transmission(TransmissionType::EsAirbornePos), state.message_type);
+    }
+    #[test]
+    fn test_merge_newer() {
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,47.65,-122.30825,,,,,,";
+        let mut state = parse(line).unwrap();
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:54.000,,,,35100,,,47.66,,,,,,,";
+        let newer = parse(line).unwrap();
+        state.merge(&newer);
+        assert_eq!(Some(35100.0), state.altitude);
+        assert_eq!(Some(47.66), state.latitude);
+        assert_eq!(Some(-122.30825), state.longitude);
+        assert_eq!(newer.generated, state.generated);
+    }
+    #[test]
+    fn test_merge_other_aircraft() {
+        let mut state = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        let expected = state.clone();
+        state.merge(&parse("MSG,1,,,3C6586,,,,,,DLH123,,,,,,,,,,,").unwrap());
+        assert_eq!(expected, state);
+        state.merge(&parse("MSG,1,,,,,,,,,DLH123,,,,,,,,,,,").unwrap());
+        assert_eq!(expected, state);
+    }
+
+    #[test]
+    fn test_squawk_string() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::SurveillanceId));
+        assert_eq!(None, message.squawk_string());
+        message.squawk = Some(7);
+        assert_eq!(Some(String::from("0007")), message.squawk_string());
+        message.squawk = Some(1200);
+        assert_eq!(Some(String::from("1200")), message.squawk_string());
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let input = "SEL,,,,,,,,,,,,,,,,,,,,,\r\n\
+                     MSG,3,,,\r\n\
+                     \r\n\
+                     CLK,,,,,,,,,,,,,,,,,,,,,\r\n";
+        let results = parse_lines(input);
+        assert_eq!(vec![
+            (1, Ok(Message::new(MessageType::SelectionChange))),
+            (2, Err(ParseError::InvalidLineFormat)),
+            (4, Ok(Message::new(MessageType::Click))),
+        ], results);
+        assert!(parse_lines("").is_empty());
+    }
+    #[test]
+    fn test_parse_iter() {
+        let input = "SEL,,,,,,,,,,,,,,,,,,,,,\r\n\
+                     MSG,3,,,\r\n\
+                     \r\n\
+                     MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,\n\
+                     CLK,,,,,,,,,,,,,,,,,,,,,";
+        let eager = parse_lines(input).into_iter().map(|(_, result)| result).collect::<Vec<_>>();
+        assert_eq!(eager, parse_iter(input).collect::<Vec<_>>());
+        assert_eq!(4, parse_iter(input).count());
+        assert_eq!(None, parse_iter("\n  \n").next());
+    }
+    #[test]
+    fn test_parse_lines_newline_in_quotes() {
+        let input = "MSG,1,,,A1B2C3,,,,,,\"DLH\n123\",,,,,,,,,,,\nSEL,,,,,,,,,,,,,,,,,,,,,\n";
+        let results = parse_lines(input);
+        assert_eq!(2, results.len());
+        assert_eq!(1, results[0].0);
+        assert_eq!(Some("DLH\n123"), results[0].1.as_ref().unwrap().callsign.as_deref());
+        assert_eq!((3, Ok(Message::new(MessageType::SelectionChange))), results[1]);
+        // A quote that is never closed is only part of its own line
+        assert_eq!(vec![(2, Err(ParseError::InvalidLineFormat)),
+                        (3, Ok(Message::new(MessageType::SelectionChange)))],
+                   parse_lines("\r\nMSG,1,,,A1B2C3,,,,,,\"DLH\r\nSEL,,,,,,,,,,,,,,,,,,,,,"));
+    }
+    #[test]
+    fn test_parse_lines_stray_quote() {
+        let input = "MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,\n\
+                     SEL,,,,,,,,,,,,,,,,,,,,,\n\
+                     CLK,,,,,,,,,,,,,,,,,,,,,\n";
+        let results = parse_lines(input);
+        assert_eq!(3, results.len());
+        assert_eq!(Some("\"DLH"), results[0].1.as_ref().unwrap().callsign.as_deref());
+        assert_eq!((2, Ok(Message::new(MessageType::SelectionChange))), results[1]);
+        assert_eq!((3, Ok(Message::new(MessageType::Click))), results[2]);
+        // Lines without delimiters are only joined up to the limit
+        let input = "MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,\na\nb\nc\nd\"\nCLK,,,,,,,,,,,,,,,,,,,,,";
+        let results = parse_lines(input);
+        assert_eq!(6, results.len());
+        assert_eq!(Some("\"DLH"), results[0].1.as_ref().unwrap().callsign.as_deref());
+        assert_eq!((6, Ok(Message::new(MessageType::Click))), results[5]);
+    }
+    #[test]
+    fn test_split_record() {
+        assert_eq!(None, split_record("", true));
+        assert_eq!(None, split_record("SEL", false));
+        assert_eq!(Some(("SEL", 5)), split_record("SEL\r\nCLK", false));
+        assert_eq!(Some(("CLK", 3)), split_record("CLK", true));
+        assert_eq!(None, split_record("MSG,\"A\nB", false));
+        assert_eq!(Some(("MSG,\"A\nB\"", 10)), split_record("MSG,\"A\nB\"\n", false));
+        assert_eq!(Some(("MSG,\"A", 7)), split_record("MSG,\"A\nB", true));
+    }
+    #[test]
+    fn test_parse_iter_newline_in_quotes() {
+        let input = "MSG,1,,,A1B2C3,,,,,,\"DLH\r\n123\",,,,,,,,,,,\r\nCLK,,,,,,,,,,,,,,,,,,,,,";
+        let results = parse_iter(input).collect::<Vec<_>>();
+        assert_eq!(2, results.len());
+        assert_eq!(Some("DLH\r\n123"), results[0].as_ref().unwrap().callsign.as_deref());
+        assert_eq!(Ok(Message::new(MessageType::Click)), results[1]);
+    }
+
+    #[test]
+    fn test_parse_with_delimiter() {
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)),
+                   parse_with_delimiter("SEL;;;;;;;;;;;;;;;;;;;;;", ';'));
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)),
+                   parse_with_delimiter("SEL\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\r\n", '\t'));
+        let message = parse_with_delimiter("MSG;3;;;A1B2C3;;;;;;;35000;;;;;;;;;;", ';').unwrap();
+        assert_eq!(Some(0xA1B2C3), message.icao_address);
+        assert_eq!(Some(35000.0), message.altitude);
+        assert_eq!(Err(ParseError::InvalidLineFormat),
+                   parse_with_delimiter("SEL,,,,,,,,,,,,,,,,,,,,,", ';'));
+    }
+
+    #[test]
+    fn test_field_count() {
+        assert_eq!(0, parse("SEL,,,,,,,,,,,,,,,,,,,,,").unwrap().field_count());
+        let line = "MSG,3,1,1,123456,1,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,\
+                    DLH123,35000,450,90,47.65,-122.30825,-64,7700,-1,-1,0,0,-30.5";
+        assert_eq!(21, parse(line).unwrap().field_count());
+        let line = "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,";
+        assert_eq!(5, parse(line).unwrap().field_count());
+        // The ICAO address, the raw HexIdent text, the callsign column, and the status
+        assert_eq!(4, parse("STA,,,,A1B2C3,,,,,,RM,,,,,,,,,,,").unwrap().field_count());
+    }
+
+    #[test]
+    fn test_dedup_key() {
+        let first = parse("MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,,,,,,,,").unwrap();
+        let repeated = parse("MSG,3,,,A1B2C3,,2016/03/11,21:24:53.900,,,,35000,,,,,,,,,,").unwrap();
+        let later = parse("MSG,3,,,A1B2C3,,2016/03/11,21:24:54.351,,,,35000,,,,,,,,,,").unwrap();
+        let key = first.dedup_key().unwrap();
+        assert_eq!(0xA1B2C3, key.0);
+        assert_eq!(Some(key), repeated.dedup_key());
+        assert!(Some(key) != later.dedup_key());
+        assert_eq!(Some(key.1 + 1), later.dedup_key().map(|key| key.1));
+        assert_eq!(None, parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().dedup_key());
+        assert_eq!(None, parse("MSG,3,,,,,2016/03/11,21:24:53.351,,,,,,,,,,,,,,").unwrap().dedup_key());
+    }
+
+    #[test]
+    fn test_is_on_ground_flag() {
+        assert_eq!(Some(true), parse("MSG,3,,,A1B2C3,,,,,,,,,,,,,,,,,-1").unwrap().is_on_ground());
+        assert_eq!(Some(false), parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,0").unwrap().is_on_ground());
+        // The flag takes precedence over the transmission type
+        assert_eq!(Some(false), parse("MSG,2,,,A1B2C3,,,,,,,,,,,,,,,,,0").unwrap().is_on_ground());
+    }
+    #[test]
+    fn test_is_on_ground_surface_position() {
+        let message = parse("MSG,2,,,A1B2C3,,,,,,,,12,270,47.45,-122.31,,,,,,").unwrap();
+        assert_eq!(None, message.on_ground);
+        assert_eq!(Some(true), message.is_on_ground());
+        assert_eq!(None, parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().is_on_ground());
+    }
+
+    #[test]
+    fn test_keep_raw() {
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,47.65,-122.30825,,,,,,\r\n";
+        assert_eq!(None, parse(line).unwrap().raw);
+        let options = ParseOptions { keep_raw: true, ..ParseOptions::default() };
+        let message = parse_with_options(line, &options).unwrap();
+        assert_eq!(Some(String::from(line)), message.raw);
+        assert_eq!(Some(35000.0), message.altitude);
+    }
+
+    #[test]
+    fn test_aircraft_status() {
+        let line = |code: &str| format!("STA,,5,179,A1B2C3,10103,2016/03/11,21:24:53.351,\
+                                         2016/03/11,21:24:53.400,{},,,,,,,,,,,", code);
+        let message = parse(&line("RM")).unwrap();
+        assert_eq!(MessageType::StatusAircraft, message.message_type);
+        assert_eq!(Some(AircraftStatus::Remove), message.status);
+        assert_eq!(Some(String::from("RM")), message.callsign);
+        assert_eq!(Some(AircraftStatus::PositionLost), parse(&line("PL")).unwrap().status);
+        assert_eq!(Some(AircraftStatus::SignalLost), parse(&line("SL")).unwrap().status);
+        assert_eq!(Some(AircraftStatus::Delete), parse(&line("AD")).unwrap().status);
+        assert_eq!(Some(AircraftStatus::Ok), parse(&line("OK")).unwrap().status);
+        assert_eq!(None, parse(&line("XX")).unwrap().status);
+        assert_eq!(None, parse(&line("")).unwrap().status);
+        assert_round_trip(message);
+    }
+    #[test]
+    fn test_aircraft_status_only_for_sta() {
+        let message = parse("MSG,1,,,A1B2C3,,,,,,OK,,,,,,,,,,,").unwrap();
+        assert_eq!(Some(String::from("OK")), message.callsign);
+        assert_eq!(None, message.status);
+    }
+
+    #[test]
+    fn test_age() {
+        let message = parse("MSG,3,,,A1B2C3,,2016/03/11,21:24:53.000,,,,35000,,,,,,,,,,").unwrap();
+        let clock = FixedClock(Local.ymd(2016, 3, 11).and_hms(21, 25, 3));
+        assert_eq!(Some(chrono::Duration::seconds(10)), message.age(&clock));
+        assert!(message.age(&SystemClock).unwrap() > chrono::Duration::days(365));
+        let undated = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, undated.age(&clock));
+    }
+    #[test]
+    fn test_is_stale() {
+        let message = parse("MSG,3,,,A1B2C3,,2016/03/11,21:24:53.000,,,,35000,,,,,,,,,,").unwrap();
+        let clock = FixedClock(Local.ymd(2016, 3, 11).and_hms(21, 25, 3));
+        assert!(!message.is_stale(chrono::Duration::seconds(10), &clock));
+        assert!(message.is_stale(chrono::Duration::seconds(9), &clock));
+        let undated = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert!(undated.is_stale(chrono::Duration::days(365), &clock));
+    }
+
+    #[test]
+    fn test_emitter_category() {
+        let line = "MSG,1,,,A1B2C3,,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,DLH123,,,,,,,,,,,,-12.5,A3";
+        let message = parse(line).unwrap();
+        assert_eq!(Some(0xA3), message.emitter_category);
+        assert_eq!(Some(-12.5), message.rssi);
+        assert_eq!(Some(String::from("DLH123")), message.callsign);
+        assert_round_trip(message.clone());
+        // The signal strength column is written, even if it is empty, to keep the category in place
+        let mut no_rssi = message;
+        no_rssi.rssi = None;
+        assert!(no_rssi.to_sbs1_string().ends_with(",,A3"));
+        assert_round_trip(no_rssi);
+
+        let line = |category: &str| format!("MSG,1,,,A1B2C3,,,,,,DLH123,,,,,,,,,,,,,{}", category);
+        assert_eq!(Some(0xB6), parse(&line("b6")).unwrap().emitter_category);
+        assert_eq!(None, parse(&line("")).unwrap().emitter_category);
+        assert_eq!(None, parse(&line("E1")).unwrap().emitter_category);
+        assert_eq!(None, parse(&line("A9")).unwrap().emitter_category);
+        let strict = ParseOptions { strict_numeric: true, ..ParseOptions::default() };
+        assert_eq!(Err(ParseError::FieldError { index: 23, field: "emitter_category" }),
+                   parse_with_options(&line("A"), &strict));
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), parse("SEL,,,,,,,,,,,,,,,,,,,,,,"));
+        let line = "MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,,-12.5,A3,";
+        let message = parse(line).unwrap();
+        assert_eq!(Some(-12.5), message.rssi);
+        assert_eq!(Some(0xA3), message.emitter_category);
+        let line = "MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,,-12.5,A3,-1,";
+        assert_eq!(Some(true), parse(line).unwrap().position_valid);
+        assert_eq!(Err(ParseError::InvalidLineFormat), parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,,-12.5,A3,-1,,"));
+    }
+
+    #[test]
+    fn test_message_type_try_from() {
+        assert_eq!(Ok(MessageType::SelectionChange), MessageType::try_from("SEL"));
+        assert_eq!(Ok(MessageType::Mlat), MessageType::try_from("MLAT"));
+        assert_eq!(Ok(MessageType::Transmission(TransmissionType::EsAirbornePos)),
+                   MessageType::try_from("MSG,3"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), MessageType::try_from("MSG"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), MessageType::try_from("MSG,9"));
+        assert_eq!(Err(ParseError::InvalidMessageType), MessageType::try_from("XYZ"));
+        assert_eq!(Err(ParseError::InvalidMessageType), MessageType::try_from("SEL,3"));
+        assert_eq!(Err(ParseError::InvalidMessageType), MessageType::try_from(""));
+    }
+    #[test]
+    fn test_transmission_type_try_from() {
+        assert_eq!(Ok(TransmissionType::EsAirbornePos), TransmissionType::try_from("3"));
+        assert_eq!(Ok(TransmissionType::AllCallReply), TransmissionType::try_from("8"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from("0"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from("9"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from("MSG"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from(""));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from("+3"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), TransmissionType::try_from("-3"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), MessageType::try_from("MSG,+3"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType),
+                   parse("MSG,+3,,,A1B2C3,,,,,,,35000,,,,,,,,,,"));
+    }
+
+    #[test]
+    fn test_timestamps_in_time_zone() {
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,35000,,,,,,,,,,";
+        let message = parse(line).unwrap();
+        let generated = message.generated_in(&UTC).unwrap();
+        assert_eq!(message.generated.unwrap().with_timezone(&UTC), generated);
+        assert_eq!(message.generated_utc(), Some(generated));
+        assert_eq!(Some(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 400)),
+                   message.logged_in(&UTC).map(|logged| logged.with_timezone(&Local)));
+        let offset = chrono::offset::fixed::FixedOffset::east(3600);
+        assert_eq!(generated.with_timezone(&offset), message.generated_in(&offset).unwrap());
+        assert_eq!(None, Message::new(MessageType::Click).generated_in(&offset));
+    }
+
+    #[test]
+    fn test_empty_message_type() {
+        let options = ParseOptions { empty_message_type: true, ..ParseOptions::default() };
+        assert_eq!(Err(ParseError::EmptyMessageType),
+                   parse_with_options(",3,,,A1B2C3,,,,,,,35000,,,,,,,,,,", &options));
+        assert_eq!(Err(ParseError::EmptyMessageType),
+                   parse_with_options("  ,,,,,,,,,,,,,,,,,,,,,", &options));
+        assert_eq!(Err(ParseError::InvalidMessageType),
+                   parse_with_options("XYZ,,,,,,,,,,,,,,,,,,,,,", &options));
+        // Without the option, an empty message type is an unknown one
+        assert_eq!(Err(ParseError::InvalidMessageType), parse(",3,,,A1B2C3,,,,,,,35000,,,,,,,,,,"));
+        assert_eq!(Err(ParseError::InvalidMessageType), parse("XYZ,,,,,,,,,,,,,,,,,,,,,"));
+        assert_eq!("Parse error: Empty message type", ParseError::EmptyMessageType.to_string());
+    }
+
+    #[test]
+    fn test_negative_altitude() {
+        let message = parse("MSG,5,,,A1B2C3,,,,,,,-50,,,,,,,,,,0").unwrap();
+        assert_eq!(Some(-50.0), message.altitude);
+        assert_eq!(Ok(()), message.validate());
+        assert_round_trip(message);
+        let floor = parse("MSG,5,,,A1B2C3,,,,,,,-1500,,,,,,,,,,").unwrap();
+        assert_eq!(Ok(()), floor.validate());
+        let corrupt = parse("MSG,5,,,A1B2C3,,,,,,,-99999,,,,,,,,,,").unwrap();
+        assert_eq!(Some(-99999.0), corrupt.altitude);
+        assert_eq!(Err(ValidationError::AltitudeOutOfRange), corrupt.validate());
+    }
+
+    #[test]
+    fn test_position() {
+        let both = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap();
+        assert_eq!(Some((47.65, -122.30825)), both.position());
+        assert!(both.has_position());
+        let latitude_only = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,,,,,,,").unwrap();
+        assert_eq!(None, latitude_only.position());
+        assert!(!latitude_only.has_position());
+        let neither = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, neither.position());
+        assert!(!neither.has_position());
+    }
+
+    #[test]
+    fn test_decimal_comma() {
+        let line = "MSG;3;;;A1B2C3;;;;;;;35000,5;;;47,65;-122,30825;;;;;;";
+        let options = ParseOptions { decimal_comma: true, ..ParseOptions::default() };
+        let message = parse_with_delimiter_and_options(line, ';', &options).unwrap();
+        assert_eq!(Some(35000.5), message.altitude);
+        assert_eq!(Some(47.65), message.latitude);
+        assert_eq!(Some(-122.30825), message.longitude);
+        // Without the option, the altitude cannot be parsed
+        assert_eq!(None, parse_with_delimiter(line, ';').unwrap().altitude);
+        let line = "MSG;3;;;A1B2C3;;;;;;;35000.5;;;;;;;;;;";
+        assert_eq!(Some(35000.5), parse_with_delimiter_and_options(line, ';', &options).unwrap().altitude);
+    }
+
+    #[test]
+    fn test_climb_state() {
+        let line = |vertical_speed: &str| format!("MSG,4,,,A1B2C3,,,,,,,,451,123,,,{},,,,,", vertical_speed);
+        assert_eq!(Some(ClimbState::Climbing), parse(&line("2000")).unwrap().climb_state());
+        assert_eq!(Some(ClimbState::Descending), parse(&line("-1500")).unwrap().climb_state());
+        assert_eq!(Some(ClimbState::Level), parse(&line("0")).unwrap().climb_state());
+        assert_eq!(Some(ClimbState::Level), parse(&line("64")).unwrap().climb_state());
+        assert_eq!(Some(ClimbState::Level), parse(&line("-64")).unwrap().climb_state());
+        assert_eq!(Some(ClimbState::Climbing), parse(&line("128")).unwrap().climb_state());
+        assert_eq!(None, parse(&line("")).unwrap().climb_state());
+    }
+
+    #[test]
+    fn test_parse_in_box() {
+        let inside = "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,";
+        let message = parse_in_box(inside, 47.0, 48.0, -123.0, -122.0).unwrap().unwrap();
+        assert_eq!(Some((47.65, -122.30825)), message.position());
+        assert_eq!(Ok(Some(message)), parse(inside).map(Some));
+        let outside = "MSG,3,,,A1B2C3,,,,,,,35000,,,51.47,-0.45,,,,,,";
+        assert_eq!(Ok(None), parse_in_box(outside, 47.0, 48.0, -123.0, -122.0));
+        let no_position = "MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,";
+        assert_eq!(Ok(None), parse_in_box(no_position, 47.0, 48.0, -123.0, -122.0));
+        assert_eq!(Err(ParseError::InvalidLineFormat), parse_in_box("MSG,3", 47.0, 48.0, -123.0, -122.0));
+        let bad_type = "MSG,9,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,";
+        assert_eq!(Err(ParseError::InvalidTransmissionType),
+                   parse_in_box(bad_type, 47.0, 48.0, -123.0, -122.0));
+    }
+
+    #[test]
+    fn test_id_overflow() {
+        let line = "MSG,3,99999999999,,A1B2C3,,,,,,,35000,,,,,,,,,,";
+        assert_eq!(None, parse(line).unwrap().session_id);
+        let options = ParseOptions { check_id_range: true, ..ParseOptions::default() };
+        assert_eq!(Err(ParseError::NumericOverflow { field: "session_id" }), parse_with_options(line, &options));
+        let line = "MSG,3,1,-1,A1B2C3,,,,,,,35000,,,,,,,,,,";
+        assert_eq!(Err(ParseError::NumericOverflow { field: "aircraft_id" }), parse_with_options(line, &options));
+        let line = "MSG,3,1,1,A1B2C3,4294967296,,,,,,35000,,,,,,,,,,";
+        assert_eq!(Err(ParseError::NumericOverflow { field: "flight_id" }), parse_with_options(line, &options));
+        // Values that are not numbers are still ignored
+        let line = "MSG,3,abc,4294967295,A1B2C3,,,,,,,35000,,,,,,,,,,";
+        let message = parse_with_options(line, &options).unwrap();
+        assert_eq!(None, message.session_id);
+        assert_eq!(Some(4294967295), message.aircraft_id);
+        assert_eq!("Parse error: Value out of range (session_id)",
+                   ParseError::NumericOverflow { field: "session_id" }.to_string());
+    }
+
+    #[test]
+    fn test_callsign_trimmed_upper() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsIdentAndCategory));
+        assert_eq!(None, message.callsign_trimmed_upper());
+        message.callsign = Some(String::from("dlh123 "));
+        assert_eq!(Some(String::from("DLH123")), message.callsign_trimmed_upper());
+        message.callsign = Some(String::from(" Ual1"));
+        assert_eq!(Some(String::from("UAL1")), message.callsign_trimmed_upper());
+        message.callsign = Some(String::from("   "));
+        assert_eq!(None, message.callsign_trimmed_upper());
+        assert_eq!(Some(String::from("DLH123")),
+                   parse("MSG,1,,,A1B2C3,,,,,,dlh123,,,,,,,,,,,").unwrap().callsign_trimmed_upper());
+    }
+
+    #[test]
+    fn test_country() {
+        assert_eq!(Some("United States"), parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().country());
+        assert_eq!(Some("Germany"), parse("MSG,3,,,3C6586,,,,,,,35000,,,,,,,,,,").unwrap().country());
+        assert_eq!(None, parse("MSG,3,,,F00000,,,,,,,35000,,,,,,,,,,").unwrap().country());
+        assert_eq!(None, Message::new(MessageType::Click).country());
+    }
+
+    #[test]
+    fn test_is_military() {
+        assert!(parse("MSG,3,,,AE1234,,,,,,,35000,,,,,,,,,,").unwrap().is_military());
+        assert!(parse("MSG,3,,,43C6F1,,,,,,,35000,,,,,,,,,,").unwrap().is_military());
+        assert!(!parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().is_military());
+        assert!(!parse("MSG,3,,,3C6586,,,,,,,35000,,,,,,,,,,").unwrap().is_military());
+        assert!(!Message::new(MessageType::Click).is_military());
+    }
+
+    #[test]
+    fn test_fields() {
+        let line = "MSG,3,1,2,A1B2C3,3,2016/03/11,21:24:53.351,,,DLH123,35000,,,47.65,-122.30825,,,,,0,,-12.5,A3";
+        let message = parse(line).unwrap();
+        let fields = message.fields();
+        let names = fields.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(vec![
+            "message_type", "session_id", "aircraft_id", "ident", "icao_address", "ident_raw",
+            "flight_id", "generated", "logged", "callsign", "altitude", "ground_speed", "track", "latitude",
+            "longitude", "vertical_speed", "squawk", "alert", "emergency", "special_position",
+            "on_ground", "rssi", "emitter_category", "position_valid", "status",
+        ], names);
+        let value = |name: &str| fields.iter().find(|field| field.0 == name).unwrap().1.clone();
+        assert_eq!(Some(String::from("Transmission (ES Airborne Position)")), value("message_type"));
+        assert_eq!(Some(String::from("A1B2C3")), value("icao_address"));
+        assert_eq!(Some(String::from("A1B2C3")), value("ident_raw"));
+        assert_eq!(message.generated.map(|generated| generated.to_rfc3339()), value("generated"));
+        assert_eq!(None, value("logged"));
+        assert_eq!(Some(String::from("35000")), value("altitude"));
+        assert_eq!(Some(String::from("false")), value("special_position"));
+        assert_eq!(Some(String::from("A3")), value("emitter_category"));
+        assert_eq!(None, value("status"));
+    }
+
+    #[test]
+    fn test_all_types() {
+        assert_eq!(6, MessageType::all_base().len());
+        assert!(MessageType::all_base().iter().all(|message_type| !message_type.is_transmission()));
+        assert_eq!(8, TransmissionType::all().len());
+        for (index, transmission_type) in TransmissionType::all().iter().enumerate() {
+            assert_eq!(index as u8 + 1, transmission_type.code());
+        }
+        for message_type in MessageType::all_base() {
+            assert_round_trip(Message::new(message_type.clone()));
+        }
+    }
+
+    #[test]
+    fn test_padded_type_tokens() {
+        let expected = MessageType::Transmission(TransmissionType::EsAirbornePos);
+        assert_eq!(expected, parse("MSG,03,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().message_type);
+        assert_eq!(expected, parse("MSG, 3 ,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().message_type);
+        assert_eq!(expected, parse(" MSG ,003,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().message_type);
+        assert_eq!(Ok(expected.clone()), MessageType::try_from(" MSG , 03 "));
+        assert_eq!(Ok(TransmissionType::EsAirbornePos), TransmissionType::try_from(" 03"));
+        assert_eq!(Err(ParseError::InvalidTransmissionType), parse("MSG,0 3,,,A1B2C3,,,,,,,35000,,,,,,,,,,"));
+    }
+
+    #[test]
+    fn test_position_kind() {
+        let surface = parse("MSG,2,,,A1B2C3,,,,,,,,12,270,47.45,-122.31,,,,,,").unwrap();
+        assert_eq!(Some(PositionKind::Surface), surface.position_kind());
+        let airborne = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap();
+        assert_eq!(Some(PositionKind::Airborne), airborne.position_kind());
+        let flagged = parse("MSG,5,,,A1B2C3,,,,,,,0,,,,,,,,,,-1").unwrap();
+        assert_eq!(Some(PositionKind::Surface), flagged.position_kind());
+        let flagged = parse("MSG,5,,,A1B2C3,,,,,,,35000,,,,,,,,,,0").unwrap();
+        assert_eq!(Some(PositionKind::Airborne), flagged.position_kind());
+        assert_eq!(None, parse("MSG,4,,,A1B2C3,,,,,,,,451,123,,,,,,,,").unwrap().position_kind());
+    }
+
+    #[test]
+    fn test_track_normalized() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel));
+        assert_eq!(None, message.track_normalized());
+        let track = |message: &mut Message, track: f64| {
+            message.track = Some(track);
+            message.track_normalized().unwrap()
+        };
+        assert_eq!(0.0, track(&mut message, 360.0));
+        assert_eq!(355.0, track(&mut message, -5.0));
+        assert_eq!(123.4, track(&mut message, 123.4));
+        assert_eq!(0.0, track(&mut message, 0.0));
+        assert_eq!(10.0, track(&mut message, 730.0));
+        assert_eq!(0.0, track(&mut message, -1e-20));
+    }
+
+    #[test]
+    fn test_parse_detailed() {
+        let line = "MSG,3,,,A1B2C3,,2016/13/11,21:24:53.351,2016/03/11,21:24:53.400,,35000,,,,,,,,,,";
+        let detailed = parse_detailed(line).unwrap();
+        assert_eq!(parse(line).unwrap(), detailed.message);
+        assert_eq!(None, detailed.message.generated);
+        assert!(detailed.message.logged.is_some());
+        assert_eq!(1, detailed.timestamp_errors.len());
+        let error = &detailed.timestamp_errors[0];
+        assert_eq!(6, error.index);
+        assert_eq!("generated_date", error.field);
+        assert!(error.to_string().starts_with("Invalid timestamp in column 6 (generated_date): "));
+        assert!(std::error::Error::source(error).is_some());
+
+        let line = "MSG,3,,,A1B2C3,,,,,junk,,35000,,,,,,,,,,";
+        let detailed = parse_detailed(line).unwrap();
+        assert_eq!(vec![8], detailed.timestamp_errors.iter().map(|error| error.index).collect::<Vec<_>>());
+        let line = "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,,,,,,,,";
+        assert!(parse_detailed(line).unwrap().timestamp_errors.is_empty());
+        assert_eq!(Err(ParseError::InvalidLineFormat), parse_detailed("MSG,3"));
+    }
+
+    #[test]
+    fn test_flag_conveniences() {
+        let set = parse("MSG,6,,,A1B2C3,,,,,,,,,,,,,7700,-1,-1,-1,").unwrap();
+        assert!(set.transponder_changed());
+        assert!(set.emergency_active());
+        assert!(set.special_position_active());
+        let unset = parse("MSG,6,,,A1B2C3,,,,,,,,,,,,,1000,0,0,0,").unwrap();
+        assert!(!unset.transponder_changed());
+        assert!(!unset.emergency_active());
+        assert!(!unset.special_position_active());
+        let missing = parse("MSG,6,,,A1B2C3,,,,,,,,,,,,,1000,,,,").unwrap();
+        assert!(!missing.transponder_changed());
+        assert!(!missing.emergency_active());
+        assert!(!missing.special_position_active());
+    }
+
+    #[test]
+    fn test_null_island() {
+        let options = ValidationOptions { reject_null_island: true };
+        let null = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,0,0,,,,,,").unwrap();
+        assert!(null.is_null_island());
+        assert_eq!(Ok(()), null.validate());
+        assert_eq!(Err(ValidationError::NullIsland), null.validate_with_options(&options));
+        let real = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap();
+        assert!(!real.is_null_island());
+        assert_eq!(Ok(()), real.validate_with_options(&options));
+        let equator = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,0,12.5,,,,,,").unwrap();
+        assert!(!equator.is_null_island());
+        let missing = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert!(!missing.is_null_island());
+    }
+
+    #[test]
+    fn test_expected_fields() {
+        let options = ParseOptions { expected_fields: 10..25, ..ParseOptions::default() };
+        let short = "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,";
+        assert_eq!(Err(ParseError::InvalidLineFormat), parse(short));
+        let message = parse_with_options(short, &options).unwrap();
+        assert_eq!(Some(0xA1B2C3), message.icao_address);
+        assert_eq!(Some(35000.0), message.altitude);
+        assert_eq!(Some((47.65, -122.30825)), message.position());
+        assert_eq!(None, message.alert);
+        assert_eq!(None, message.on_ground);
+        let standard = "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,0";
+        assert_eq!(parse(standard), parse_with_options(standard, &options));
+        let narrow = ParseOptions { expected_fields: 22..23, ..ParseOptions::default() };
+        assert_eq!(Err(ParseError::InvalidLineFormat),
+                   parse_with_options("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,,-12.5", &narrow));
+    }
+
+    #[test]
+    fn test_ident_raw() {
+        let message = parse("MSG,3,,,ABC,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, message.ident);
+        assert_eq!(Some(String::from("ABC")), message.ident_raw);
+        let message = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(Some(String::from("A1B2C3")), message.ident_raw);
+        let message = parse("MSG,3,,,XYZ-1,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, message.icao_address);
+        assert_eq!(Some(String::from("XYZ-1")), message.ident_raw);
+        assert_eq!("MSG,3,,,XYZ-1,,,,,,,35000,,,,,,,,,,", message.to_sbs1_string());
+        assert_eq!(None, parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,").unwrap().ident_raw);
+    }
+
+    #[test]
+    fn test_same_aircraft() {
+        let message = parse("MSG,3,,5,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap();
+        let same = parse("MSG,4,,6,A1B2C3,,,,,,,,451,123,,,,,,,,").unwrap();
+        assert_eq!(Some(true), message.same_aircraft(&same));
+        let other = parse("MSG,3,,5,3C6586,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(Some(false), message.same_aircraft(&other));
+        let no_icao = parse("MSG,3,,5,,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(Some(true), message.same_aircraft(&no_icao));
+        let neither = parse("MSG,3,,,,,,,,,,35000,,,,,,,,,,").unwrap();
+        assert_eq!(None, message.same_aircraft(&neither));
+        assert_eq!(None, neither.same_aircraft(&neither));
+    }
+
+    #[test]
+    fn test_new_aircraft_first_seen() {
+        let line = "AIR,,5,179,A1B2C3,10103,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,,,,,,,,,,,";
+        let message = parse(line).unwrap();
+        assert_eq!(MessageType::NewAircraft, message.message_type);
+        assert_eq!(Some(0xA1B2C3), message.icao_address);
+        assert_eq!(Some(179), message.aircraft_id);
+        assert_eq!(Some(10103), message.flight_id);
+        assert_eq!(Some(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 351)), message.generated);
+        assert_eq!(Some(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 400)), message.logged);
+        assert_round_trip(message);
+    }
+
+    #[test]
+    fn test_is_position_report() {
+        assert!(parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap().is_position_report());
+        assert!(parse("MSG,2,,,A1B2C3,,,,,,,,12,270,47.45,-122.31,,,,,,").unwrap().is_position_report());
+        assert!(!parse("MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,").unwrap().is_position_report());
+        assert!(!parse("MSG,4,,,A1B2C3,,,,,,,,451,123,,,,,,,,").unwrap().is_position_report());
+        assert!(!parse("MLAT,,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap().is_position_report());
+    }
+
+    #[test]
+    fn test_altitude_corrected() {
+        let message = parse("MSG,3,,,A1B2C3,,,,,,,5000,,,,,,,,,,").unwrap();
+        assert_eq!(Some(5000.0), message.altitude_corrected(1013.25));
+        let low = message.altitude_corrected(1000.0).unwrap();
+        assert!((low - 4642.25).abs() < 1e-9);
+        let near_standard = message.altitude_corrected(1013.0).unwrap();
+        assert!((near_standard - 4993.25).abs() < 1e-9);
+        assert!(low < near_standard);
+        assert_eq!(None, Message::new(MessageType::Click).altitude_corrected(1000.0));
+    }
+
+    #[test]
+    fn test_clicked_aircraft() {
+        let click = parse("CLK,,5,179,A1B2C3,10103,2016/03/11,21:24:53.351,2016/03/11,21:24:53.400,,,,,,,,,,,,").unwrap();
+        assert_eq!(MessageType::Click, click.message_type);
+        assert_eq!(Some(179), click.aircraft_id);
+        assert_eq!(Some(179), click.clicked_aircraft());
+        assert_eq!(None, parse("CLK,,,,,,,,,,,,,,,,,,,,,").unwrap().clicked_aircraft());
+        assert_eq!(None, parse("SEL,,5,179,A1B2C3,,,,,,,,,,,,,,,,,").unwrap().clicked_aircraft());
+    }
+
+    #[test]
+    fn test_summary_line() {
+        let line = "MSG,3,,,A1B2C3,,,,,,DLH123,35000,450,123,50.1,8.5,,,,,,";
+        assert_eq!("MSG3 A1B2C3 DLH123 35000ft 450kt hdg123 @ 50.1,8.5", parse(line).unwrap().summary_line());
+        let summary = parse("MSG,4,,,3C6586,,,,,,,,451.5,90,,,-1088,,,,,").unwrap().summary_line();
+        let tokens = summary.split(' ').collect::<Vec<_>>();
+        assert_eq!(vec!["MSG4", "3C6586", "451.5kt", "hdg90"], tokens);
+        assert_eq!("SEL", Message::new(MessageType::SelectionChange).summary_line());
+    }
+
+    #[test]
+    fn test_millisecond_output() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.generated = Some(Local.ymd(2016, 3, 11).and_hms_nano(21, 24, 53, 351_234_567));
+        message.logged = Some(Local.ymd(2016, 3, 11).and_hms(21, 24, 54));
+        let line = message.to_sbs1_string();
+        assert_eq!("MSG,3,,,,,2016/03/11,21:24:53.351,2016/03/11,21:24:54.000,,,,,,,,,,,,", line);
+        let parsed = parse(&line).unwrap();
+        assert_eq!(Some(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 53, 351)), parsed.generated);
+        assert_eq!(message.logged, parsed.logged);
+        assert_eq!(line, parsed.to_sbs1_string());
+    }
+
+    #[test]
+    fn test_numeric_strip_nonnumeric() {
+        let options = ParseOptions { numeric_strip_nonnumeric: true, ..ParseOptions::default() };
+        let line = "MSG,3,,,A1B2C3,,,,,,,35000ft,451.5 kt,,47.65,-122.30825,,,,,,,-12.5dBm";
+        assert_eq!(None, parse(line).unwrap().altitude);
+        let message = parse_with_options(line, &options).unwrap();
+        assert_eq!(Some(35000.0), message.altitude);
+        assert_eq!(Some(451.5), message.ground_speed);
+        assert_eq!(Some(-12.5), message.rssi);
+        assert_eq!(Some(47.65), message.latitude);
+        let strict = ParseOptions { strict_numeric: true, ..options };
+        assert_eq!(Err(ParseError::FieldError { index: 11, field: "altitude" }),
+                   parse_with_options("MSG,3,,,A1B2C3,,,,,,,ft,,,,,,,,,,", &strict));
+    }
+
+    #[test]
+    fn test_require_fields() {
+        let message = parse("MSG,3,,,A1B2C3,,,,,,DLH123,35000,,,47.65,-122.30825,,,,,,").unwrap();
+        assert_eq!(Ok((47.65, -122.30825)), message.require_position());
+        assert_eq!(Ok(0xA1B2C3), message.require_icao());
+        assert_eq!(Ok(35000.0), message.require_altitude());
+        assert_eq!(Ok("DLH123"), message.require_callsign());
+
+        let empty = parse("MSG,3,,,,,,,,,,,,,,-122.30825,,,,,,").unwrap();
+        assert_eq!(Err(MissingField { field: "latitude" }), empty.require_position());
+        assert_eq!(Err(MissingField { field: "icao_address" }), empty.require_icao());
+        assert_eq!(Err(MissingField { field: "altitude" }), empty.require_altitude());
+        assert_eq!(Err(MissingField { field: "callsign" }), empty.require_callsign());
+        assert_eq!("Missing field: latitude", empty.require_position().unwrap_err().to_string());
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_missing_field_serde() {
+        let empty = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        let errors = [
+            empty.require_position().unwrap_err(),
+            empty.require_icao().unwrap_err(),
+            empty.require_altitude().unwrap_err(),
+            empty.require_callsign().unwrap_err(),
+        ];
+        for error in &errors {
+            let json = serde_json::to_string(error).unwrap();
+            assert_eq!(*error, serde_json::from_str::<MissingField>(&json).unwrap());
+        }
+        assert!(serde_json::from_str::<MissingField>(r#"{"field":"generated_date"}"#).is_err());
+    }
+
+    #[test]
+    fn test_track_compass() {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirborneVel));
+        assert_eq!(None, message.track_compass());
+        let compass = |message: &mut Message, track: f64| {
+            message.track = Some(track);
+            message.track_compass().unwrap()
+        };
+        assert_eq!("N", compass(&mut message, 0.0));
+        assert_eq!("E", compass(&mut message, 90.0));
+        assert_eq!("SSW", compass(&mut message, 200.0));
+        assert_eq!("NNE", compass(&mut message, 11.25));
+        assert_eq!("N", compass(&mut message, 355.0));
+        assert_eq!("N", compass(&mut message, 360.0));
+        assert_eq!("NW", compass(&mut message, -45.0));
+    }
+
+    #[test]
+    fn test_validate_time_order() {
+        let line = |generated: &str, logged: &str| {
+            format!("MSG,3,,,A1B2C3,,2016/03/11,{},2016/03/11,{},,35000,,,,,,,,,,", generated, logged)
+        };
+        assert_eq!(Ok(()), parse(&line("21:24:53.351", "21:24:53.400")).unwrap().validate());
+        assert_eq!(Ok(()), parse(&line("21:24:53.351", "21:24:53.351")).unwrap().validate());
+        assert_eq!(Err(ValidationError::TimeOrder),
+                   parse(&line("21:24:53.400", "21:24:53.351")).unwrap().validate());
+        assert_eq!(Ok(()), parse(&line("21:24:53.400", "")).unwrap().validate());
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error;
+
+        let detailed = parse_detailed("MSG,3,,,A1B2C3,,2016/13/11,21:24:53.351,,,,35000,,,,,,,,,,").unwrap();
+        let error: &dyn Error = &detailed.timestamp_errors[0];
+        let source = error.source().unwrap();
+        assert!(source.downcast_ref::<chrono::format::ParseError>().is_some());
+        assert_eq!(detailed.timestamp_errors[0].error.to_string(), source.to_string());
+
+        let parse_error: Box<dyn Error> = Box::new(parse("MSG,3,,,").unwrap_err());
+        assert!(parse_error.source().is_none());
+        assert_eq!("Parse error: Invalid line format", parse_error.to_string());
+        assert!(parse_error.downcast_ref::<ParseError>().is_some());
+        assert_eq!("Validation error: Generated after logged", ValidationError::TimeOrder.to_string());
+    }
+
+    #[test]
+    fn test_position_valid() {
+        let line = |flag: &str| format!("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,0,-12.5,A3,{}", flag);
+        let valid = parse(&line("-1")).unwrap();
+        assert_eq!(Some(true), valid.position_valid);
+        assert_eq!(Some(0xA3), valid.emitter_category);
+        assert_round_trip(valid);
+        assert_eq!(Some(false), parse(&line("0")).unwrap().position_valid);
+        assert_eq!(None, parse(&line("")).unwrap().position_valid);
+        assert_eq!(None, parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,0").unwrap().position_valid);
+        // The flag is written with empty columns before it if needed
+        let mut flagged = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        flagged.position_valid = Some(false);
+        assert_eq!("MSG,3,,,,,,,,,,,,,,,,,,,,,,,0", flagged.to_sbs1_string());
+        assert_round_trip(flagged);
+    }
+
+    #[test]
+    fn test_transmission_expected_fields() {
+        assert_eq!(&["callsign"], TransmissionType::EsIdentAndCategory.expected_fields());
+        let position = TransmissionType::EsAirbornePos.expected_fields();
+        assert!(position.contains(&"latitude") && position.contains(&"longitude"));
+        assert!(position.contains(&"altitude"));
+        assert!(!position.contains(&"callsign"));
+        assert_eq!(&["ground_speed", "track", "vertical_speed"],
+                   TransmissionType::EsAirborneVel.expected_fields());
+        // Every name is the name of a field of Message
+        let message = parse("MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,").unwrap();
+        let names = message.fields().iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        for transmission_type in TransmissionType::all() {
+            for field in transmission_type.expected_fields() {
+                assert!(names.contains(field), "{}", field);
+            }
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let line = |logged: &str| {
+            format!("MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,2016/03/11,{},,35000,,,47.65,-122.30825,,,,,,", logged)
+        };
+        let message = parse(&line("21:24:53.400")).unwrap();
+        assert_eq!(message.content_hash(), parse(&line("21:24:54.012")).unwrap().content_hash());
+        assert_eq!(message.content_hash(), parse(&line("")).unwrap().content_hash());
+        let mut moved = message.clone();
+        moved.latitude = Some(47.66);
+        assert_ne!(message.content_hash(), moved.content_hash());
+        let mut later = message.clone();
+        later.generated = parse_date_time("2016/03/11", "21:24:53.352").ok();
+        assert_ne!(message.content_hash(), later.content_hash());
+        // A field that is missing does not hash the same as an empty one
+        let mut empty_callsign = message.clone();
+        empty_callsign.callsign = Some(String::new());
+        assert_ne!(message.content_hash(), empty_callsign.content_hash());
+        let mut lowercase = message.clone();
+        lowercase.ident_raw = Some(String::from("a1b2c3"));
+        assert_ne!(message.content_hash(), lowercase.content_hash());
+    }
+    #[test]
+    fn test_quoted_delimiter() {
+        let message = parse("MSG,1,,,A1B2C3,,,,,,\"DLH,123\",,,,,,,,,,,").unwrap();
+        assert_eq!(Some("DLH,123".to_string()), message.callsign);
+        assert_eq!(message, parse(&message.to_sbs1_string()).unwrap());
+        let message = parse("MSG,1,,,A1B2C3,,,,,, \"A\"\",B\" ,,,,,,,,,,,").unwrap();
+        assert_eq!(Some("A\",B".to_string()), message.callsign);
+        // Without a closing quote, the quote is not special
+        let message = parse("MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,").unwrap();
+        assert_eq!(Some("\"DLH".to_string()), message.callsign);
+        assert!(has_unclosed_quote("MSG,1,,,A1B2C3,,,,,,\"DLH"));
+        assert!(!has_unclosed_quote("MSG,1,,,A1B2C3,,,,,,\"DLH\",A\"B"));
+    }
 }
@@ -0,0 +1,96 @@
+
+use super::{parse_borrowed_with_options, Message, ParseError, ParseOptions, DEFAULT_DELIMITER};
+
+/// A parser that writes into an existing message, for parsing many lines without allocating
+///
+/// The fields of each line are split into a fixed-size array on the stack, so no buffer is
+/// needed for them. Parsing into the same message for every line reuses its strings.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    /// The options used for every line
+    options: ParseOptions,
+    /// The character that separates the fields
+    delimiter: char,
+}
+
+impl Parser {
+    /// Creates a parser with the default options
+    pub fn new() -> Parser {
+        Parser::with_options(ParseOptions::default())
+    }
+
+    /// Creates a parser with the provided options
+    pub fn with_options(options: ParseOptions) -> Parser {
+        Parser {
+            options,
+            delimiter: DEFAULT_DELIMITER,
+        }
+    }
+
+    /// Sets the character that separates the fields, instead of a comma
+    pub fn delimiter(mut self, delimiter: char) -> Parser {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Parses a line of text, replacing all the fields of the provided message
+    ///
+    /// If the line cannot be parsed, the message is not changed.
+    pub fn parse_into(&mut self, message_string: &str, message: &mut Message) -> Result<(), ParseError> {
+        let parsed = parse_borrowed_with_options(message_string, self.delimiter, &self.options)?;
+        parsed.copy_into(message);
+        Ok(())
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::String;
+    use super::*;
+    use super::super::{parse, MessageType};
+
+    #[test]
+    fn test_parse_into() {
+        let mut parser = Parser::new();
+        let mut message = Message::new(MessageType::Click);
+        let first = "MSG,1,,,A1B2C3,,,,,,DLH123,,,,,,,,,,,";
+        parser.parse_into(first, &mut message).unwrap();
+        assert_eq!(parse(first).unwrap(), message);
+        let callsign = message.callsign.as_ref().unwrap().as_ptr();
+
+        // Fields from the first line are reset, and the callsign string is reused
+        let second = "MSG,1,,,3C6586,,,,,,UAL1,,,,,,,,,,,";
+        parser.parse_into(second, &mut message).unwrap();
+        assert_eq!(parse(second).unwrap(), message);
+        assert_eq!(callsign, message.callsign.as_ref().unwrap().as_ptr());
+
+        let third = "MSG,3,,,3C6586,,,,,,,35000,,,,,,,,,,";
+        parser.parse_into(third, &mut message).unwrap();
+        assert_eq!(parse(third).unwrap(), message);
+        assert_eq!(None, message.callsign);
+    }
+    #[test]
+    fn test_parse_into_error() {
+        let mut parser = Parser::new();
+        let mut message = parse("MSG,1,,,A1B2C3,,,,,,DLH123,,,,,,,,,,,").unwrap();
+        let expected = message.clone();
+        assert_eq!(Err(ParseError::InvalidLineFormat), parser.parse_into("MSG,3", &mut message));
+        assert_eq!(expected, message);
+    }
+    #[test]
+    fn test_options_and_delimiter() {
+        let options = ParseOptions { keep_raw: true, ..ParseOptions::default() };
+        let mut parser = Parser::with_options(options).delimiter(';');
+        let mut message = Message::new(MessageType::Click);
+        parser.parse_into("SEL;;;;;;;;;;;;;;;;;;;;;", &mut message).unwrap();
+        assert_eq!(MessageType::SelectionChange, message.message_type);
+        assert_eq!(Some(String::from("SEL;;;;;;;;;;;;;;;;;;;;;")), message.raw);
+    }
+}
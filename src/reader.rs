@@ -0,0 +1,145 @@
+
+#[cfg(feature = "flate2")]
+use std::fs::File;
+#[cfg(feature = "flate2")]
+use std::io::{self, BufReader, Read};
+use std::io::BufRead;
+#[cfg(feature = "flate2")]
+use std::path::Path;
+#[cfg(feature = "flate2")]
+use flate2::read::GzDecoder;
+use super::{parse, split_record, Message, ParseError};
+
+/// Reads messages from a source of lines, such as a BaseStation TCP connection
+///
+/// Each line is parsed as it is read. Blank lines are skipped. A quoted field that has no
+/// closing quote continues on the next line, so that line is read into the same message, up to
+/// a limit of a few lines. Iteration ends at the end of the input, or when reading from the
+/// input fails.
+pub struct MessageReader<R> {
+    /// The source of lines
+    reader: R,
+    /// The text that has been read but not parsed yet, reused to avoid allocating a new buffer
+    /// for every line
+    line: String,
+    /// True once the end of the input has been reached, or reading from it has failed
+    finished: bool,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    /// Creates a reader that reads messages from the provided source
+    pub fn new(reader: R) -> MessageReader<R> {
+        MessageReader {
+            reader,
+            line: String::new(),
+            finished: false,
+        }
+    }
+}
+
+/// Opens a gzip-compressed log file, and returns a reader that reads messages from it
+#[cfg(feature = "flate2")]
+pub fn open_sbs1_gz(path: &Path) -> io::Result<MessageReader<impl BufRead>> {
+    Ok(read_sbs1_gz(File::open(path)?))
+}
+
+/// Returns a reader that reads messages from a source of gzip-compressed lines
+///
+/// Iteration ends if the data is not valid gzip, in the same way as when reading fails.
+#[cfg(feature = "flate2")]
+pub fn read_sbs1_gz<R: Read>(compressed: R) -> MessageReader<impl BufRead> {
+    MessageReader::new(BufReader::new(GzDecoder::new(compressed)))
+}
+
+impl<R: BufRead> Iterator for MessageReader<R> {
+    type Item = Result<Message, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match split_record(&self.line, self.finished) {
+                Some((record, length)) => {
+                    let result = if record.trim().is_empty() { None } else { Some(parse(record)) };
+                    self.line.drain(..length);
+                    if result.is_some() {
+                        return result;
+                    }
+                }
+                None if self.finished => return None,
+                None => match self.reader.read_line(&mut self.line) {
+                    Ok(0) | Err(_) => self.finished = true,
+                    Ok(_) => {}
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    #[test]
+    fn test_read_lines() {
+        let input: &[u8] = b"SEL,,,,,,,,,,,,,,,,,,,,,\r\n\
+                             \r\n\
+                             MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,\n\
+                             bad line\n\
+                             CLK,,,,,,,,,,,,,,,,,,,,,";
+        let messages = MessageReader::new(Cursor::new(input)).collect::<Vec<_>>();
+        assert_eq!(4, messages.len());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), messages[0]);
+        let position = messages[1].clone().unwrap();
+        assert_eq!(MessageType::Transmission(TransmissionType::EsAirbornePos), position.message_type);
+        assert_eq!(Some(35000.0), position.altitude);
+        assert_eq!(Err(ParseError::InvalidLineFormat), messages[2]);
+        assert_eq!(Ok(Message::new(MessageType::Click)), messages[3]);
+    }
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_read_gz() {
+        use std::io::Write;
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"SEL,,,,,,,,,,,,,,,,,,,,,\r\nMSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,\r\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+        let messages = read_sbs1_gz(&compressed[..]).collect::<Vec<_>>();
+        assert_eq!(2, messages.len());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), messages[0]);
+        assert_eq!(Some(35000.0), messages[1].clone().unwrap().altitude);
+        // Data that is not compressed is not read
+        assert_eq!(0, read_sbs1_gz(&b"SEL,,,,,,,,,,,,,,,,,,,,,"[..]).count());
+        assert!(open_sbs1_gz(Path::new("/nonexistent/feed.gz")).is_err());
+    }
+    #[test]
+    fn test_read_empty() {
+        let input: &[u8] = b"";
+        assert_eq!(0, MessageReader::new(input).count());
+    }
+    #[test]
+    fn test_read_newline_in_quotes() {
+        let input: &[u8] = b"MSG,1,,,A1B2C3,,,,,,\"DLH\n123\",,,,,,,,,,,\n\
+                             CLK,,,,,,,,,,,,,,,,,,,,,\n\
+                             MSG,1,,,A1B2C3,,,,,,\"DLH";
+        let messages = MessageReader::new(Cursor::new(input)).collect::<Vec<_>>();
+        assert_eq!(3, messages.len());
+        assert_eq!(Some("DLH\n123".to_string()), messages[0].clone().unwrap().callsign);
+        assert_eq!(Ok(Message::new(MessageType::Click)), messages[1]);
+        // A quote that is never closed ends at the end of the input
+        assert_eq!(Err(ParseError::InvalidLineFormat), messages[2]);
+    }
+    #[test]
+    fn test_read_stray_quote() {
+        let input: &[u8] = b"MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,\n\
+                             SEL,,,,,,,,,,,,,,,,,,,,,\n\
+                             CLK,,,,,,,,,,,,,,,,,,,,,\n";
+        let messages = MessageReader::new(Cursor::new(input)).collect::<Vec<_>>();
+        assert_eq!(3, messages.len());
+        assert_eq!(Some("\"DLH"), messages[0].as_ref().unwrap().callsign.as_deref());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), messages[1]);
+        assert_eq!(Ok(Message::new(MessageType::Click)), messages[2]);
+    }
+}
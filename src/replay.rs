@@ -0,0 +1,95 @@
+
+use std::thread;
+use std::time::Duration;
+use chrono::datetime::DateTime;
+use chrono::offset::local::Local;
+use super::Message;
+
+/// Replays recorded messages with the same timing as when they were generated
+///
+/// Before yielding each message, the replayer sleeps for the time between the generated time
+/// of that message and the generated time of the previous message, divided by the speed.
+/// Messages without a generated time, or generated before the previous message, are yielded
+/// immediately.
+pub struct Replayer<I> {
+    /// The messages to replay
+    messages: I,
+    /// How many times faster than real time the messages are replayed
+    speed: f64,
+    /// The latest generated time of the messages yielded so far
+    previous: Option<DateTime<Local>>,
+}
+
+impl<I: Iterator<Item = Message>> Replayer<I> {
+    /// Creates a replayer for the provided messages
+    ///
+    /// A speed of 1.0 replays in real time, and 10.0 replays ten times faster. If the speed is
+    /// not positive, the messages are yielded without waiting.
+    pub fn new<T: IntoIterator<IntoIter = I, Item = Message>>(messages: T, speed: f64) -> Replayer<I> {
+        Replayer {
+            messages: messages.into_iter(),
+            speed,
+            previous: None,
+        }
+    }
+
+    /// Returns the time to wait before yielding a message generated at the provided time
+    fn delay(&self, generated: DateTime<Local>) -> Option<Duration> {
+        let previous = self.previous?;
+        let gap = (generated - previous).num_nanoseconds()?;
+        if gap <= 0 || self.speed <= 0.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(gap as f64 / 1e9 / self.speed))
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for Replayer<I> {
+    type Item = Message;
+
+    fn next(&mut self) -> Option<Message> {
+        let message = self.messages.next()?;
+        if let Some(generated) = message.generated {
+            if let Some(delay) = self.delay(generated) {
+                thread::sleep(delay);
+            }
+            if self.previous.is_none_or(|previous| generated > previous) {
+                self.previous = Some(generated);
+            }
+        }
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::offset::TimeZone;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    /// Creates a position message from an aircraft, generated at the provided millisecond
+    fn position(icao_address: u32, millisecond: u32) -> Message {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.icao_address = Some(icao_address);
+        message.generated = Some(Local.ymd(2016, 3, 11).and_hms_milli(21, 24, 0, millisecond));
+        message
+    }
+
+    #[test]
+    fn test_order_preserved() {
+        let messages = vec![
+            position(1, 0),
+            position(2, 500),
+            Message::new(MessageType::Click),
+            position(3, 200),
+            position(4, 999),
+        ];
+        let replayed = Replayer::new(messages.clone(), 1000.0).collect::<Vec<_>>();
+        assert_eq!(messages, replayed);
+    }
+    #[test]
+    fn test_no_speed() {
+        let messages = vec![position(1, 0), position(2, 999)];
+        assert_eq!(messages.clone(), Replayer::new(messages, 0.0).collect::<Vec<_>>());
+    }
+}
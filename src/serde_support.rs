@@ -0,0 +1,81 @@
+//! Serialization helpers for types that do not map directly onto serde
+
+/// Serializes optional DateTimes as RFC 3339 strings
+#[cfg(feature = "chrono")]
+pub mod date_time {
+    use chrono::datetime::DateTime;
+    use chrono::offset::local::Local;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<DateTime<Local>>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *value {
+            Some(ref date_time) => serializer.serialize_some(&date_time.to_rfc3339()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
+        where D: Deserializer<'de>
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(text) => DateTime::parse_from_rfc3339(&text)
+                .map(|date_time| Some(date_time.with_timezone(&Local)))
+                .map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Serializes DateTimes that are always present as RFC 3339 strings
+#[cfg(feature = "chrono")]
+pub mod required_date_time {
+    use chrono::datetime::DateTime;
+    use chrono::offset::local::Local;
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        super::date_time::serialize(&Some(*value), serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Local>, D::Error>
+        where D: Deserializer<'de>
+    {
+        super::date_time::deserialize(deserializer)?
+            .ok_or_else(|| D::Error::custom("missing date and time"))
+    }
+}
+
+/// Deserializes a field name, mapping it back to the matching static name
+pub fn field_name<'de, D>(deserializer: D) -> Result<&'static str, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    static_name(deserializer, super::FIELD_NAMES.iter().cloned())
+}
+
+/// Deserializes the name of a field of `Message`, mapping it back to the matching static name
+pub fn message_field_name<'de, D>(deserializer: D) -> Result<&'static str, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    let fields = super::Message::new(super::MessageType::Click).fields();
+    static_name(deserializer, fields.into_iter().map(|(name, _)| name))
+}
+
+/// Deserializes a name, and returns the matching name from the provided names
+fn static_name<'de, D, I>(deserializer: D, names: I) -> Result<&'static str, D::Error>
+    where D: ::serde::Deserializer<'de>,
+          I: IntoIterator<Item = &'static str>
+{
+    use serde::de::Error;
+    use serde::Deserialize;
+
+    let name = String::deserialize(deserializer)?;
+    names.into_iter()
+        .find(|&field| field == name)
+        .ok_or_else(|| D::Error::custom(format!("unknown field name {}", name)))
+}
@@ -0,0 +1,74 @@
+
+use super::Message;
+
+/// Sorts messages by their generated times, with messages that have no generated time last
+///
+/// The sort is stable, so messages with the same time stay in the same order.
+#[cfg(feature = "chrono")]
+pub fn sort_by_time(messages: &mut [Message]) {
+    messages.sort_by_key(|message| (message.generated.is_none(), message.generated));
+}
+
+/// Sorts messages by their ICAO addresses, with messages that have no ICAO address last
+///
+/// The sort is stable, so the messages from each aircraft stay in the same order.
+pub fn sort_by_aircraft(messages: &mut [Message]) {
+    messages.sort_by_key(|message| (message.icao_address.is_none(), message.icao_address));
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    /// Creates a message from an aircraft, with an aircraft ID to identify it
+    fn message(icao_address: Option<u32>, aircraft_id: u32) -> Message {
+        let mut message = Message::new(MessageType::Transmission(TransmissionType::EsAirbornePos));
+        message.icao_address = icao_address;
+        message.aircraft_id = Some(aircraft_id);
+        message
+    }
+
+    /// Returns the aircraft IDs of some messages, in order
+    fn ids(messages: &[Message]) -> Vec<u32> {
+        messages.iter().filter_map(|message| message.aircraft_id).collect()
+    }
+
+    #[test]
+    fn test_sort_by_aircraft() {
+        let mut messages = vec![
+            message(Some(0xA1B2C3), 1),
+            message(None, 2),
+            message(Some(0x3C6586), 3),
+            message(Some(0xA1B2C3), 4),
+            message(Some(0x3C6586), 5),
+            message(None, 6),
+        ];
+        sort_by_aircraft(&mut messages);
+        assert_eq!(vec![3, 5, 1, 4, 2, 6], ids(&messages));
+    }
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_sort_by_time() {
+        use chrono::offset::local::Local;
+        use chrono::offset::TimeZone;
+
+        let at = |second: Option<u32>, aircraft_id: u32| {
+            let mut message = message(Some(0xA1B2C3), aircraft_id);
+            message.generated = second.map(|second| Local.ymd(2016, 3, 11).and_hms(21, 24, second));
+            message
+        };
+        let mut messages = vec![
+            at(Some(30), 1),
+            at(None, 2),
+            at(Some(10), 3),
+            at(Some(30), 4),
+            at(None, 5),
+            at(Some(20), 6),
+        ];
+        sort_by_time(&mut messages);
+        assert_eq!(vec![3, 6, 1, 4, 2, 5], ids(&messages));
+    }
+}
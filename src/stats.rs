@@ -0,0 +1,176 @@
+
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "chrono")]
+use chrono::datetime::DateTime;
+#[cfg(feature = "chrono")]
+use chrono::offset::local::Local;
+use super::{Message, MessageType, ParseError};
+
+/// Statistics about the messages in a feed
+///
+/// Each parse result is recorded with `record`. Transmissions are counted separately for each
+/// transmission type, and field errors are counted separately for each field.
+#[derive(Debug, Clone, Default)]
+pub struct FeedStats {
+    /// The number of messages of each type
+    message_counts: HashMap<MessageType, usize>,
+    /// The number of each parse error
+    error_counts: HashMap<ParseError, usize>,
+    /// The ICAO addresses of all aircraft seen
+    aircraft: HashSet<u32>,
+    /// The earliest generated time of all messages
+    #[cfg(feature = "chrono")]
+    first: Option<DateTime<Local>>,
+    /// The latest generated time of all messages
+    #[cfg(feature = "chrono")]
+    last: Option<DateTime<Local>>,
+}
+
+impl FeedStats {
+    /// Creates statistics with nothing recorded
+    pub fn new() -> FeedStats {
+        FeedStats::default()
+    }
+
+    /// Records the result of parsing one line
+    pub fn record(&mut self, result: &Result<Message, ParseError>) {
+        let message = match *result {
+            Ok(ref message) => message,
+            Err(ref error) => {
+                *self.error_counts.entry(error.clone()).or_insert(0) += 1;
+                return;
+            }
+        };
+        *self.message_counts.entry(message.message_type.clone()).or_insert(0) += 1;
+        if let Some(icao_address) = message.icao_address {
+            self.aircraft.insert(icao_address);
+        }
+        #[cfg(feature = "chrono")]
+        {
+            if let Some(generated) = message.generated {
+                if self.first.is_none_or(|first| generated < first) {
+                    self.first = Some(generated);
+                }
+                if self.last.is_none_or(|last| generated > last) {
+                    self.last = Some(generated);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of messages of each type
+    pub fn message_counts(&self) -> &HashMap<MessageType, usize> {
+        &self.message_counts
+    }
+
+    /// Returns the number of times each parse error occurred
+    pub fn error_counts(&self) -> &HashMap<ParseError, usize> {
+        &self.error_counts
+    }
+
+    /// Returns the total number of messages that were parsed successfully
+    pub fn message_total(&self) -> usize {
+        self.message_counts.values().sum()
+    }
+
+    /// Returns the total number of parse errors
+    pub fn error_total(&self) -> usize {
+        self.error_counts.values().sum()
+    }
+
+    /// Returns the number of different aircraft, by ICAO address
+    pub fn aircraft_count(&self) -> usize {
+        self.aircraft.len()
+    }
+
+    /// Returns the earliest generated time of all messages
+    #[cfg(feature = "chrono")]
+    pub fn first_generated(&self) -> Option<DateTime<Local>> {
+        self.first
+    }
+
+    /// Returns the latest generated time of all messages
+    #[cfg(feature = "chrono")]
+    pub fn last_generated(&self) -> Option<DateTime<Local>> {
+        self.last
+    }
+
+    /// Returns a one-line description of the statistics, such as
+    /// "120 messages, 3 errors, 14 aircraft"
+    ///
+    /// If any messages had generated times, the range of times is added in RFC 3339 format.
+    pub fn summary(&self) -> String {
+        let summary = format!("{} messages, {} errors, {} aircraft",
+                              self.message_total(), self.error_total(), self.aircraft_count());
+        #[cfg(feature = "chrono")]
+        {
+            if let (Some(first), Some(last)) = (self.first, self.last) {
+                return format!("{}, from {} to {}", summary, first.to_rfc3339(), last.to_rfc3339());
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse, TransmissionType};
+
+    /// Records the results of parsing some lines
+    fn record_lines(lines: &[&str]) -> FeedStats {
+        let mut stats = FeedStats::new();
+        for line in lines {
+            stats.record(&parse(line));
+        }
+        stats
+    }
+
+    #[test]
+    fn test_empty() {
+        let stats = FeedStats::new();
+        assert_eq!(0, stats.message_total());
+        assert_eq!(0, stats.error_total());
+        assert_eq!("0 messages, 0 errors, 0 aircraft", stats.summary());
+    }
+    #[test]
+    fn test_mixed_batch() {
+        let stats = record_lines(&[
+            "SEL,,,,,,,,,,,,,,,,,,,,,",
+            "MSG,3,,,A1B2C3,,,,,,,35000,,,47.65,-122.30825,,,,,,",
+            "MSG,3,,,A1B2C3,,,,,,,35100,,,47.66,-122.30825,,,,,,",
+            "MSG,4,,,3C6586,,,,,,,,451,123,,,,,,,,",
+            "MSG,9,,,3C6586,,,,,,,,,,,,,,,,,",
+            "bad line",
+            "also bad",
+        ]);
+        assert_eq!(4, stats.message_total());
+        assert_eq!(Some(&1), stats.message_counts().get(&MessageType::SelectionChange));
+        assert_eq!(Some(&2), stats.message_counts()
+            .get(&MessageType::Transmission(TransmissionType::EsAirbornePos)));
+        assert_eq!(Some(&1), stats.message_counts()
+            .get(&MessageType::Transmission(TransmissionType::EsAirborneVel)));
+        assert_eq!(3, stats.error_total());
+        assert_eq!(Some(&2), stats.error_counts().get(&ParseError::InvalidLineFormat));
+        assert_eq!(Some(&1), stats.error_counts().get(&ParseError::InvalidTransmissionType));
+        assert_eq!(2, stats.aircraft_count());
+        assert_eq!("4 messages, 3 errors, 2 aircraft", stats.summary());
+    }
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_time_range() {
+        let stats = record_lines(&[
+            "MSG,3,,,A1B2C3,,2016/03/11,21:24:53.351,,,,35000,,,,,,,,,,",
+            "MSG,3,,,A1B2C3,,2016/03/11,21:24:51.000,,,,35000,,,,,,,,,,",
+            "MSG,3,,,A1B2C3,,2016/03/11,21:24:52.000,,,,35000,,,,,,,,,,",
+            "MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,",
+        ]);
+        let first = stats.first_generated().unwrap();
+        let last = stats.last_generated().unwrap();
+        assert_eq!(super::super::timestamp::parse_date_time("2016/03/11", "21:24:51.000").ok(), Some(first));
+        assert_eq!(super::super::timestamp::parse_date_time("2016/03/11", "21:24:53.351").ok(), Some(last));
+        assert_eq!(format!("4 messages, 0 errors, 1 aircraft, from {} to {}",
+                           first.to_rfc3339(), last.to_rfc3339()),
+                   stats.summary());
+    }
+}
@@ -0,0 +1,94 @@
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, Lines};
+use super::{parse, split_record, Message, ParseError};
+
+/// Asynchronously reads messages from a source of lines, such as a BaseStation TCP connection
+///
+/// This is the asynchronous equivalent of `MessageReader`. Each line is parsed as it is read,
+/// and blank lines are skipped. Lines are joined when a quoted field contains line breaks, in
+/// the same way as `MessageReader` joins them. The stream ends at the end of the input, or
+/// when reading from the input fails.
+pub struct MessageStream<R> {
+    /// The lines of the input
+    lines: Lines<R>,
+    /// The lines that have been read but not parsed yet, each followed by a newline
+    pending: String,
+    /// True once the end of the input has been reached, or reading from it has failed
+    finished: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> MessageStream<R> {
+    /// Creates a stream that reads messages from the provided source
+    pub fn new(reader: R) -> MessageStream<R> {
+        MessageStream { lines: reader.lines(), pending: String::new(), finished: false }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for MessageStream<R> {
+    type Item = Result<Message, ParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let stream = self.get_mut();
+        loop {
+            if let Some((record, length)) = split_record(&stream.pending, stream.finished) {
+                let result = if record.trim().is_empty() { None } else { Some(parse(record)) };
+                stream.pending.drain(..length);
+                match result {
+                    Some(result) => return Poll::Ready(Some(result)),
+                    None => continue,
+                }
+            }
+            if stream.finished {
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut stream.lines).poll_next_line(cx) {
+                Poll::Ready(Ok(Some(line))) => {
+                    stream.pending.push_str(&line);
+                    stream.pending.push('\n');
+                }
+                Poll::Ready(Ok(None)) | Poll::Ready(Err(_)) => stream.finished = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on_stream;
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    #[test]
+    fn test_stream_lines() {
+        let input: &[u8] = b"SEL,,,,,,,,,,,,,,,,,,,,,\r\n\
+                             \n\
+                             MSG,3,,,A1B2C3,,,,,,,35000,,,,,,,,,,\n\
+                             MSG,9,,,,,,,,,,,,,,,,,,,,";
+        let messages = block_on_stream(MessageStream::new(input)).collect::<Vec<_>>();
+        assert_eq!(3, messages.len());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), messages[0]);
+        let position = messages[1].clone().unwrap();
+        assert_eq!(MessageType::Transmission(TransmissionType::EsAirbornePos), position.message_type);
+        assert_eq!(Some(35000.0), position.altitude);
+        assert_eq!(Err(ParseError::InvalidTransmissionType), messages[2]);
+    }
+    #[test]
+    fn test_stream_newline_in_quotes() {
+        let input: &[u8] = b"MSG,1,,,A1B2C3,,,,,,\"DLH\r\n123\",,,,,,,,,,,\r\n\
+                             MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,\n\
+                             SEL,,,,,,,,,,,,,,,,,,,,,\n\
+                             CLK,,,,,,,,,,,,,,,,,,,,,";
+        let messages = block_on_stream(MessageStream::new(input)).collect::<Vec<_>>();
+        assert_eq!(4, messages.len());
+        assert_eq!(Some("DLH\n123"), messages[0].as_ref().unwrap().callsign.as_deref());
+        // A quote that is never closed is part of the callsign, and the lines after it are
+        // still read
+        assert_eq!(Some("\"DLH"), messages[1].as_ref().unwrap().callsign.as_deref());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), messages[2]);
+        assert_eq!(Ok(Message::new(MessageType::Click)), messages[3]);
+    }
+}
@@ -0,0 +1,122 @@
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use super::{parse, split_record, Message, ParseError};
+
+/// Parses messages from chunks of bytes that may not end at line boundaries, such as the
+/// data read from a TCP socket
+///
+/// Only complete lines, ending with a newline, are parsed. The bytes after the last newline
+/// are kept until the rest of the line arrives. Blank lines are skipped. A newline inside a
+/// quoted field does not end the line, up to a limit of a few lines.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingParser {
+    /// The bytes of the incomplete line at the end of the data pushed so far
+    buffer: Vec<u8>,
+}
+
+impl StreamingParser {
+    /// Creates a parser with no buffered data
+    pub fn new() -> StreamingParser {
+        StreamingParser::default()
+    }
+
+    /// Adds bytes to the parser, and returns the results of parsing each line that they complete
+    ///
+    /// A line that is not valid UTF-8 results in `ParseError::InvalidLineFormat`.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Vec<Result<Message, ParseError>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut results = Vec::new();
+        // The start of the text that has not been parsed yet
+        let mut start = 0;
+        loop {
+            let rest = &self.buffer[start..];
+            let (text, valid) = match core::str::from_utf8(rest) {
+                Ok(text) => (text, true),
+                Err(error) => {
+                    (core::str::from_utf8(&rest[..error.valid_up_to()]).unwrap_or_default(), false)
+                }
+            };
+            match split_record(text, false) {
+                Some((record, length)) => {
+                    if !record.trim().is_empty() {
+                        results.push(parse(record));
+                    }
+                    start += length;
+                }
+                // The message that is not complete yet has bytes that are not valid UTF-8 in
+                // it, so it is invalid once its line ends
+                None if !valid => match rest[text.len()..].iter().position(|&byte| byte == b'\n') {
+                    Some(index) => {
+                        results.push(Err(ParseError::InvalidLineFormat));
+                        start += text.len() + index + 1;
+                    }
+                    None => break,
+                },
+                None => break,
+            }
+        }
+        self.buffer.drain(..start);
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{MessageType, TransmissionType};
+
+    #[test]
+    fn test_split_line() {
+        let mut parser = StreamingParser::new();
+        assert_eq!(vec![Ok(Message::new(MessageType::SelectionChange))],
+                   parser.push_bytes(b"SEL,,,,,,,,,,,,,,,,,,,,,\r\nMSG,3,,,A1B2C3,,,,,,,35"));
+        let results = parser.push_bytes(b"000,,,,,,,,,,\r\n");
+        assert_eq!(1, results.len());
+        let message = results[0].clone().unwrap();
+        assert_eq!(MessageType::Transmission(TransmissionType::EsAirbornePos), message.message_type);
+        assert_eq!(Some(35000.0), message.altitude);
+    }
+    #[test]
+    fn test_incomplete_line() {
+        let mut parser = StreamingParser::new();
+        assert!(parser.push_bytes(b"SEL,,,,,,,,,").is_empty());
+        assert!(parser.push_bytes(b",,,,,,,,,,,,").is_empty());
+        assert_eq!(vec![Ok(Message::new(MessageType::SelectionChange))], parser.push_bytes(b"\n"));
+        assert!(parser.push_bytes(b"").is_empty());
+    }
+    #[test]
+    fn test_multiple_lines() {
+        let mut parser = StreamingParser::new();
+        let results = parser.push_bytes(b"SEL,,,,,,,,,,,,,,,,,,,,,\n\nbad line\n\xff\nCLK,,,,,,,,,,,,,,,,,,,,,\n");
+        assert_eq!(vec![
+            Ok(Message::new(MessageType::SelectionChange)),
+            Err(ParseError::InvalidLineFormat),
+            Err(ParseError::InvalidLineFormat),
+            Ok(Message::new(MessageType::Click)),
+        ], results);
+    }
+    #[test]
+    fn test_newline_in_quotes() {
+        let mut parser = StreamingParser::new();
+        assert!(parser.push_bytes(b"MSG,1,,,A1B2C3,,,,,,\"DLH\n").is_empty());
+        let results = parser.push_bytes(b"123\",,,,,,,,,,,\nSEL,,,,,,,,,,,,,,,,,,,,,\n");
+        assert_eq!(2, results.len());
+        assert_eq!(Some("DLH\n123"), results[0].as_ref().unwrap().callsign.as_deref());
+        assert_eq!(Ok(Message::new(MessageType::SelectionChange)), results[1]);
+    }
+    #[test]
+    fn test_stray_quote() {
+        let mut parser = StreamingParser::new();
+        assert!(parser.push_bytes(b"MSG,1,,,A1B2C3,,,,,,\"DLH,,,,,,,,,,,\n").is_empty());
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            results.extend(parser.push_bytes(b"SEL,,,,,,,,,,,,,,,,,,,,,\n"));
+        }
+        assert_eq!(4, results.len());
+        assert_eq!(Some("\"DLH"), results[0].as_ref().unwrap().callsign.as_deref());
+        let selection_change = Ok(Message::new(MessageType::SelectionChange));
+        assert!(results[1..].iter().all(|result| *result == selection_change));
+        assert!(parser.buffer.is_empty());
+    }
+}
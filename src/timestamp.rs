@@ -0,0 +1,153 @@
+
+use chrono::Duration;
+use chrono::datetime::DateTime;
+use chrono::offset::local::Local;
+use chrono::offset::utc::UTC;
+use chrono::offset::TimeZone;
+use std::error::Error;
+use std::fmt;
+use super::{parse, split_fields, Clock, Message, ParseError, ParseOptions, DEFAULT_DELIMITER,
+            DEFAULT_EXPECTED_FIELDS, FIELD_NAMES};
+
+/// The expected format for combined times and dates
+const DATE_TIME_FORMAT: &str = "%Y/%m/%d %H:%M:%S%.f";
+/// The format for combined times and dates that some loggers use, without fractional seconds
+const DATE_TIME_FORMAT_WHOLE_SECONDS: &str = "%Y/%m/%d %H:%M:%S";
+/// The date part of DATE_TIME_FORMAT, used when writing messages
+const DATE_FORMAT: &str = "%Y/%m/%d";
+/// The time format used when writing messages, with milliseconds like BaseStation
+///
+/// Any precision beyond milliseconds is cut off, and whole seconds are written with `.000`.
+const TIME_FORMAT: &str = "%H:%M:%S%.3f";
+
+impl Message {
+    /// Returns the time when the message was generated, in UTC
+    pub fn generated_utc(&self) -> Option<DateTime<UTC>> {
+        self.generated_in(&UTC)
+    }
+
+    /// Returns the time when the message was logged, in UTC
+    pub fn logged_utc(&self) -> Option<DateTime<UTC>> {
+        self.logged_in(&UTC)
+    }
+
+    /// Returns the time when the message was generated, in the provided time zone
+    pub fn generated_in<Tz: TimeZone>(&self, time_zone: &Tz) -> Option<DateTime<Tz>> {
+        self.generated.as_ref().map(|generated| generated.with_timezone(time_zone))
+    }
+
+    /// Returns the time when the message was logged, in the provided time zone
+    pub fn logged_in<Tz: TimeZone>(&self, time_zone: &Tz) -> Option<DateTime<Tz>> {
+        self.logged.as_ref().map(|logged| logged.with_timezone(time_zone))
+    }
+
+    /// Returns the time that has passed since the message was generated, according to the
+    /// provided clock, or None if the generated time is not known
+    pub fn age(&self, clock: &impl Clock) -> Option<Duration> {
+        self.generated.map(|generated| clock.now() - generated)
+    }
+
+    /// Returns true if the message was generated longer ago than the provided duration,
+    /// according to the provided clock
+    ///
+    /// A message with no generated time is always stale.
+    pub fn is_stale(&self, max: Duration, clock: &impl Clock) -> bool {
+        self.age(clock).is_none_or(|age| age > max)
+    }
+
+    /// Returns a key for detecting duplicate messages, made from the ICAO address and the
+    /// generated time as a Unix timestamp in whole seconds
+    ///
+    /// Returns None if either the address or the time is missing.
+    pub fn dedup_key(&self) -> Option<(u32, i64)> {
+        match (self.icao_address, self.generated) {
+            (Some(icao_address), Some(generated)) => Some((icao_address, generated.timestamp())),
+            _ => None,
+        }
+    }
+}
+
+/// A message, with the reasons that any of its timestamps could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailedMessage {
+    /// The parsed message
+    pub message: Message,
+    /// The errors from parsing the generated and logged timestamps, in the order of the fields
+    pub timestamp_errors: Vec<TimestampError>,
+}
+
+/// An error from parsing the date and time fields of a timestamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampError {
+    /// The zero-based index of the date field in the line
+    pub index: usize,
+    /// The name of the date field
+    pub field: &'static str,
+    /// The reason that the date and time could not be parsed
+    pub error: chrono::format::ParseError,
+}
+
+impl fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid timestamp in column {} ({}): {}", self.index, self.field, self.error)
+    }
+}
+
+impl Error for TimestampError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Parses a line of text into a message, and records why any timestamps could not be parsed
+///
+/// The message is the same as the one that `parse` returns. A timestamp that cannot be parsed
+/// is None in the message, as usual, and does not cause an error. Timestamps with empty date and
+/// time fields are not errors.
+pub fn parse_detailed(message_string: &str) -> Result<DetailedMessage, ParseError> {
+    let message = parse(message_string)?;
+    let (parts, _) = split_fields(message_string, DEFAULT_DELIMITER, &DEFAULT_EXPECTED_FIELDS)?;
+    let timestamp_errors = [6, 8].iter()
+        .filter(|&&index| !parts[index].is_empty() || !parts[index + 1].is_empty())
+        .filter_map(|&index| parse_date_time(parts[index], parts[index + 1]).err().map(|error| {
+            TimestampError { index, field: FIELD_NAMES[index], error }
+        }))
+        .collect();
+    Ok(DetailedMessage { message, timestamp_errors })
+}
+
+/// Parses a date field and a time field into a DateTime, in the time zone that the options
+/// specify
+///
+/// Returns None if the fields cannot be parsed.
+pub fn parse_timestamp(date: &str, time: &str, options: &ParseOptions) -> Option<DateTime<Local>> {
+    if options.utc_timestamps {
+        parse_date_time_in(&UTC, date, time).ok().map(|date_time| date_time.with_timezone(&Local))
+    } else {
+        parse_date_time(date, time).ok()
+    }
+}
+
+/// Parses a date component and a time component into a DateTime
+pub fn parse_date_time(date: &str, time: &str) -> Result<DateTime<Local>, chrono::format::ParseError> {
+    parse_date_time_in(&Local, date, time)
+}
+
+/// Parses a date component and a time component into a DateTime in the provided time zone
+///
+/// The time may have fractional seconds, or whole seconds only.
+fn parse_date_time_in<Tz: TimeZone>(time_zone: &Tz, date: &str, time: &str)
+                                    -> Result<DateTime<Tz>, chrono::format::ParseError> {
+    let combined = format!("{} {}", date.trim(), time.trim());
+    time_zone.datetime_from_str(&combined, DATE_TIME_FORMAT)
+        .or_else(|_| time_zone.datetime_from_str(&combined, DATE_TIME_FORMAT_WHOLE_SECONDS))
+}
+
+/// Formats an optional DateTime into separate date and time components
+pub fn format_date_time(date_time: &Option<DateTime<Local>>) -> (String, String) {
+    match *date_time {
+        Some(ref date_time) => (date_time.format(DATE_FORMAT).to_string(),
+                                date_time.format(TIME_FORMAT).to_string()),
+        None => (String::new(), String::new()),
+    }
+}